@@ -0,0 +1,231 @@
+use arrow_array::builder::{
+    Date32Builder, StringBuilder, StringDictionaryBuilder, UInt32Builder, UInt64Builder,
+};
+use arrow_array::types::Int8Type;
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+use chrono::NaiveDate;
+use indicatif::ProgressBar;
+use mysql::Value;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::data;
+use crate::sql::{self, ColumnType, SqlRow};
+
+/*
+ * Arrow record batches and Parquet files for the ham_* records and the joined licenses.
+ *
+ * Columns are named as in SQL.  The identifier is a UInt32, the U64Null fields are nullable
+ * UInt64s, the MM/DD/YYYY dates are nullable Date32s, operator class and license status are
+ * dictionary encoded, and everything else is a string.
+ */
+
+// Rows per record batch, and so per Parquet row group.
+const BATCH_SIZE: usize = 65536;
+
+enum ColumnBuilder {
+    Identifier(UInt32Builder),
+    NullableInteger(UInt64Builder),
+    Date(Date32Builder),
+    Code(StringDictionaryBuilder<Int8Type>),
+    Text(StringBuilder),
+}
+
+fn is_code(column: &str) -> bool {
+    matches!(column, "Operator Class" | "License Status")
+}
+
+fn is_date(column: &str) -> bool {
+    column.ends_with(" Date")
+}
+
+fn data_type(column: &str) -> DataType {
+    match sql::column_type(column) {
+        ColumnType::Integer => DataType::UInt32,
+        ColumnType::NullableInteger => DataType::UInt64,
+        ColumnType::Text if is_date(column) => DataType::Date32,
+        ColumnType::Text if is_code(column) => {
+            DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8))
+        }
+        ColumnType::Text => DataType::Utf8,
+    }
+}
+
+pub fn schema<T: SqlRow>() -> SchemaRef {
+    let fields: Vec<Field> = T::COLUMNS
+        .iter()
+        .map(|(_field, column)| {
+            let data_type = data_type(column);
+            let nullable = matches!(data_type, DataType::UInt64 | DataType::Date32);
+            Field::new(*column, data_type, nullable)
+        })
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::NULL => String::new(),
+        Value::Bytes(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        other => other.as_sql(true).trim_matches('\'').to_string(),
+    }
+}
+
+fn integer(value: &Value) -> Option<u64> {
+    match value {
+        Value::UInt(value) => Some(*value),
+        Value::Int(value) => u64::try_from(*value).ok(),
+        _ => None,
+    }
+}
+
+// Days since 1970-01-01, or None for a blank or malformed date.
+fn date32(value: &Value) -> Option<i32> {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1)?;
+    let date = NaiveDate::parse_from_str(&text(value), "%m/%d/%Y").ok()?;
+    i32::try_from(date.signed_duration_since(epoch).num_days()).ok()
+}
+
+impl ColumnBuilder {
+    fn new(data_type: &DataType) -> Self {
+        match data_type {
+            DataType::UInt32 => ColumnBuilder::Identifier(UInt32Builder::new()),
+            DataType::UInt64 => ColumnBuilder::NullableInteger(UInt64Builder::new()),
+            DataType::Date32 => ColumnBuilder::Date(Date32Builder::new()),
+            DataType::Dictionary(_, _) => ColumnBuilder::Code(StringDictionaryBuilder::new()),
+            _ => ColumnBuilder::Text(StringBuilder::new()),
+        }
+    }
+
+    fn append(&mut self, value: &Value) {
+        match self {
+            ColumnBuilder::Identifier(builder) => {
+                builder.append_value(integer(value).unwrap_or_default() as u32)
+            }
+            ColumnBuilder::NullableInteger(builder) => builder.append_option(integer(value)),
+            ColumnBuilder::Date(builder) => builder.append_option(date32(value)),
+            ColumnBuilder::Code(builder) => builder.append_value(text(value)),
+            ColumnBuilder::Text(builder) => builder.append_value(text(value)),
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Identifier(builder) => Arc::new(builder.finish()),
+            ColumnBuilder::NullableInteger(builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Date(builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Code(builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Text(builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+// One record batch from rows of SqlRow::values().
+fn values_batch<T: SqlRow>(
+    rows: impl IntoIterator<Item = Vec<Value>>,
+) -> Result<RecordBatch, ArrowError> {
+    let schema = schema::<T>();
+    let mut builders: Vec<ColumnBuilder> = schema
+        .fields()
+        .iter()
+        .map(|field| ColumnBuilder::new(field.data_type()))
+        .collect();
+    for values in rows {
+        for (builder, value) in builders.iter_mut().zip(values.iter()) {
+            builder.append(value);
+        }
+    }
+    let columns: Vec<ArrayRef> = builders.iter_mut().map(ColumnBuilder::finish).collect();
+    RecordBatch::try_new(schema, columns)
+}
+
+// Record batches of at most BATCH_SIZE rows each.
+fn values_batches<T: SqlRow>(
+    rows: impl Iterator<Item = Vec<Value>>,
+) -> impl Iterator<Item = Result<RecordBatch, ArrowError>> {
+    let mut rows = rows.peekable();
+    std::iter::from_fn(move || {
+        rows.peek()?;
+        Some(values_batch::<T>(rows.by_ref().take(BATCH_SIZE)))
+    })
+}
+
+/*
+ * One record batch of licenses or of ham_* records, for querying in process, e.g.
+ * record_batch(&licenses) or record_batch(&fcc_db.entity).
+ */
+pub fn record_batch<T: SqlRow>(records: &[T]) -> Result<RecordBatch, ArrowError> {
+    values_batch::<T>(records.iter().map(SqlRow::values))
+}
+
+// Record batches of at most BATCH_SIZE records each, such as the licenses from iter_licenses.
+pub fn record_batches<T: SqlRow>(
+    records: impl Iterator<Item = T>,
+) -> impl Iterator<Item = Result<RecordBatch, ArrowError>> {
+    values_batches::<T>(records.map(|record| record.values()))
+}
+
+fn write_table<T: SqlRow>(
+    directory: &str,
+    count: Option<usize>,
+    rows: impl Iterator<Item = Vec<Value>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let filename = Path::new(directory).join(format!("{}.parquet", T::TABLE));
+    println!("Writing {}", filename.display());
+    let tmp_filename = filename.with_extension("parquet.tmp");
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(
+        File::create(&tmp_filename)?,
+        schema::<T>(),
+        Some(properties),
+    )?;
+
+    let pb = match count {
+        Some(count) => ProgressBar::new(count.try_into().unwrap()),
+        None => ProgressBar::no_length(),
+    };
+    for batch in values_batches::<T>(rows) {
+        let batch = batch?;
+        writer.write(&batch)?;
+        pb.inc(batch.num_rows().try_into().unwrap());
+    }
+    pb.finish();
+
+    writer.into_inner()?.sync_all()?;
+    std::fs::rename(&tmp_filename, &filename)?;
+    Ok(())
+}
+
+pub fn write_parquet(
+    directory: &str,
+    amateur: &[data::Amateur],
+    entity: &[data::Entity],
+    application_license_header: &[data::ApplicationLicenseHeader],
+    licenses: impl Iterator<Item = crate::License>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(directory)?;
+    write_table::<data::Amateur>(
+        directory,
+        Some(amateur.len()),
+        amateur.iter().map(SqlRow::values),
+    )?;
+    write_table::<data::Entity>(
+        directory,
+        Some(entity.len()),
+        entity.iter().map(SqlRow::values),
+    )?;
+    write_table::<data::ApplicationLicenseHeader>(
+        directory,
+        Some(application_license_header.len()),
+        application_license_header.iter().map(SqlRow::values),
+    )?;
+    write_table::<crate::License>(directory, None, licenses.map(|license| license.values()))?;
+    Ok(())
+}
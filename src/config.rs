@@ -1,16 +1,304 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_threads() -> usize {
+    10
+}
+
+fn default_batch_size() -> usize {
+    10000
+}
+
+fn default_max_connections() -> usize {
+    10
+}
+
+fn default_sql_dump_filename() -> String {
+    String::from("fccdb.sql")
+}
+
+fn default_parquet_directory() -> String {
+    String::from("parquet")
+}
+
+fn default_dat_filename() -> String {
+    String::from("fccdb.dat")
+}
+
+fn default_index_filename() -> String {
+    String::from("fccdb.idx")
+}
+
+fn default_sqlite_filename() -> String {
+    String::from("fccdb.sqlite")
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LoadMode {
+    // Reload the whole table through a staging table.
+    #[default]
+    Full,
+    // Apply only the rows that changed since the last load.
+    Sync,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LicenseOutput {
+    // No ham_license, only the raw ham_AM, ham_EN and ham_HD tables.
+    #[default]
+    None,
+    // A ham_license table, loaded after the raw tables.
+    Table,
+    // A ham_license view joining the raw tables.
+    View,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinMode {
+    // Only licenses with an AM, a licensee EN and an HD row.
+    #[default]
+    Inner,
+    // Every license in AM or HD, with blank fields for the missing rows.
+    Left,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum JsonFormat {
+    // One pretty-printed JSON array of licenses.
+    #[default]
+    Array,
+    // JSON Lines: one compact license object per line.
+    Ndjson,
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct MySQLConfig {
     pub sql_url: String,
+    #[serde(default)]
+    pub load_mode: LoadMode,
+    // Load chunks with LOAD DATA LOCAL INFILE, falling back to INSERT if the server refuses.
+    #[serde(default)]
+    pub bulk_load: bool,
+    // Retries per chunk for deadlocks, lock wait timeouts and lost connections.
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default)]
+    pub license_output: LicenseOutput,
+    // Loader threads, rows per chunk, and the connection pool size shared by the threads.
+    #[serde(default = "default_threads")]
+    pub threads: usize,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    #[serde(default = "default_max_connections")]
+    pub max_connections: usize,
+}
+
+fn default_output_profile() -> String {
+    "full".to_string()
+}
+
+fn default_csv_directory() -> String {
+    String::from("csv")
+}
+
+fn default_csv_delimiter() -> char {
+    ','
+}
+
+fn default_csv_encoding() -> String {
+    String::from("utf-8")
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CsvConfig {
+    #[serde(default = "default_csv_directory")]
+    pub directory: String,
+    #[serde(default = "default_csv_delimiter")]
+    pub delimiter: char,
+    // Any WHATWG encoding label, e.g. utf-8 or windows-1252.
+    #[serde(default = "default_csv_encoding")]
+    pub encoding: String,
+}
+
+impl Default for CsvConfig {
+    fn default() -> Self {
+        CsvConfig {
+            directory: default_csv_directory(),
+            delimiter: default_csv_delimiter(),
+            encoding: default_csv_encoding(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactAction {
+    // Leave the field blank.
+    Drop,
+    // Replace all but the last four characters with '*'.
+    Mask,
+    // Replace with a keyed hash that is the same on every run with the same key.
+    Hash,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct RedactionConfig {
+    // The HMAC key for hash; keep it between runs so hashed values stay comparable.
+    #[serde(default)]
+    pub key_file: Option<String>,
+    #[serde(default)]
+    pub fields: HashMap<String, RedactAction>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyTarget {
+    // One line per change.
+    Stdout,
+    // The run's changes as JSON in notifications_file.
+    File,
+    // The same JSON POSTed to webhook_url, when anything changed.
+    Webhook,
+}
+
+fn default_watch_state_file() -> String {
+    String::from("watchlist_state.json")
+}
+
+fn default_notifications_file() -> String {
+    String::from("notifications.json")
+}
+
+fn default_notify() -> Vec<NotifyTarget> {
+    vec![NotifyTarget::Stdout]
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WatchlistConfig {
+    #[serde(default)]
+    pub callsigns: Vec<String>,
+    #[serde(default)]
+    pub frns: Vec<String>,
+    // Matched against the start of the licensee's ZIP code.
+    #[serde(default)]
+    pub zip_codes: Vec<String>,
+    #[serde(default = "default_watch_state_file")]
+    pub state_file: String,
+    #[serde(default = "default_notify")]
+    pub notify: Vec<NotifyTarget>,
+    #[serde(default = "default_notifications_file")]
+    pub notifications_file: String,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+fn default_window_days() -> i64 {
+    90
+}
+
+fn default_grace_days() -> i64 {
+    730
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExpiringConfig {
+    // Days ahead of today to look for expiring licenses.
+    #[serde(default = "default_window_days")]
+    pub window_days: i64,
+    // Days after expiry a license can still be renewed.
+    #[serde(default = "default_grace_days")]
+    pub grace_days: i64,
+    #[serde(default)]
+    pub states: Vec<String>,
+    // Matched against the start of the licensee's ZIP code.
+    #[serde(default)]
+    pub zip_codes: Vec<String>,
+    // A file of member call signs, one per line.
+    #[serde(default)]
+    pub roster: Option<String>,
+}
+
+impl Default for ExpiringConfig {
+    fn default() -> Self {
+        ExpiringConfig {
+            window_days: default_window_days(),
+            grace_days: default_grace_days(),
+            states: Vec::new(),
+            zip_codes: Vec::new(),
+            roster: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PostgresConfig {
+    pub connection_string: String,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Configuration {
     pub mysql_config: MySQLConfig,
+    #[serde(default)]
+    pub postgres_config: Option<PostgresConfig>,
     pub json_filename: String,
+    #[serde(default)]
+    pub json_format: JsonFormat,
+    #[serde(default)]
+    pub join: JoinMode,
     pub write_sql: bool,
+    #[serde(default)]
+    pub write_postgres: bool,
     pub write_json: bool,
+    #[serde(default)]
+    pub write_sqlite: bool,
+    #[serde(default = "default_sqlite_filename")]
+    pub sqlite_filename: String,
+    #[serde(default)]
+    pub write_csv: bool,
+    #[serde(default)]
+    pub csv_config: CsvConfig,
+    #[serde(default)]
+    pub write_parquet: bool,
+    #[serde(default = "default_parquet_directory")]
+    pub parquet_directory: String,
+    #[serde(default)]
+    pub write_index: bool,
+    #[serde(default = "default_index_filename")]
+    pub index_filename: String,
+    #[serde(default)]
+    pub write_sql_dump: bool,
+    #[serde(default = "default_sql_dump_filename")]
+    pub sql_dump_filename: String,
     pub write_dat: bool,
+    #[serde(default = "default_dat_filename")]
+    pub dat_filename: String,
+    // Read the records from this snapshot instead of downloading and parsing the .dat files.
+    #[serde(default)]
+    pub input_snapshot: Option<String>,
+    // Conditions every exported or loaded license must meet; see filter.rs.
+    #[serde(default)]
+    pub filter: Vec<String>,
+    // full, callbook, or a name from profiles; see profile.rs.
+    #[serde(default = "default_output_profile")]
+    pub output_profile: String,
+    #[serde(default)]
+    pub profiles: HashMap<String, Vec<String>>,
+    // Fields to drop, mask or hash in every output; see redact.rs.
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    // Licenses to report changes for after each run; see watch.rs.
+    #[serde(default)]
+    pub watchlist: Option<WatchlistConfig>,
+    // The `expiring` report; see expiring.rs.
+    #[serde(default)]
+    pub expiring: ExpiringConfig,
     pub download_db: bool,
 }
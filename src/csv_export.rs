@@ -0,0 +1,267 @@
+use encoding_rs::{EncoderResult, Encoding, UTF_8};
+use indicatif::ProgressBar;
+use mysql::Value;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::config::CsvConfig;
+use crate::data;
+use crate::profile::Profile;
+use crate::sql::SqlRow;
+
+/*
+ * CSV export of the raw ham_* records and the joined licenses.
+ *
+ * One file per table, named after it, with a header row of the SQL column names and the same
+ * values the SQL loaders write; ham_license.csv has just the output profile's columns.  Rows
+ * are written as UTF-8 and transcoded to the configured encoding; characters it cannot
+ * represent become '?'.
+ */
+
+fn text(value: Value) -> String {
+    match value {
+        Value::NULL => String::new(),
+        Value::Bytes(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Value::Int(value) => value.to_string(),
+        Value::UInt(value) => value.to_string(),
+        other => other.as_sql(true).trim_matches('\'').to_string(),
+    }
+}
+
+// Passes the UTF-8 written by the csv writer on in another encoding.
+struct Transcoder<W: Write> {
+    out: W,
+    encoding: &'static Encoding,
+    pending: Vec<u8>,
+    encoded: Vec<u8>,
+}
+
+impl<W: Write> Transcoder<W> {
+    fn into_inner(mut self) -> std::io::Result<W> {
+        self.flush()?;
+        Ok(self.out)
+    }
+}
+
+impl<W: Write> Write for Transcoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.encoding == UTF_8 {
+            return self.out.write(buf);
+        }
+        self.pending.extend_from_slice(buf);
+        // A write may end partway through a character; keep those bytes for the next one.
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(why) => why.valid_up_to(),
+        };
+        let text = std::str::from_utf8(&self.pending[..valid]).unwrap();
+        let mut encoder = self.encoding.new_encoder();
+        let mut input = text;
+        self.encoded.clear();
+        loop {
+            self.encoded.reserve(input.len() + 16);
+            let (result, read) =
+                encoder.encode_from_utf8_to_vec_without_replacement(input, &mut self.encoded, true);
+            input = &input[read..];
+            match result {
+                EncoderResult::InputEmpty => break,
+                EncoderResult::OutputFull => (),
+                EncoderResult::Unmappable(_) => self.encoded.push(b'?'),
+            }
+        }
+        self.out.write_all(&self.encoded)?;
+        self.pending.drain(..valid);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+fn write_table<T: SqlRow>(
+    csv_config: &CsvConfig,
+    encoding: &'static Encoding,
+    columns: &[(&str, &str)],
+    count: Option<usize>,
+    rows: impl Iterator<Item = Vec<Value>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let filename = Path::new(&csv_config.directory).join(format!("{}.csv", T::TABLE));
+    println!("Writing {}", filename.display());
+    let tmp_filename = filename.with_extension("csv.tmp");
+    let transcoder = Transcoder {
+        out: BufWriter::new(File::create(&tmp_filename)?),
+        encoding,
+        pending: Vec::new(),
+        encoded: Vec::new(),
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(csv_config.delimiter as u8)
+        .from_writer(transcoder);
+
+    writer.write_record(columns.iter().map(|(_field, column)| column))?;
+    let pb = match count {
+        Some(count) => ProgressBar::new(count.try_into().unwrap()),
+        None => ProgressBar::no_length(),
+    };
+    for values in rows {
+        writer.write_record(values.into_iter().map(text))?;
+        pb.inc(1);
+    }
+    pb.finish();
+
+    let out = writer.into_inner().map_err(|why| why.into_error())?;
+    out.into_inner()?.into_inner()?.sync_all()?;
+    std::fs::rename(&tmp_filename, &filename)?;
+    Ok(())
+}
+
+pub fn write_csv(
+    csv_config: &CsvConfig,
+    amateur: &[data::Amateur],
+    entity: &[data::Entity],
+    application_license_header: &[data::ApplicationLicenseHeader],
+    profile: &Profile,
+    licenses: impl Iterator<Item = crate::License>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !csv_config.delimiter.is_ascii() {
+        return Err(format!("csv delimiter {:?} is not ASCII", csv_config.delimiter).into());
+    }
+    let encoding = Encoding::for_label(csv_config.encoding.as_bytes())
+        .ok_or(format!("unknown csv encoding {}", csv_config.encoding))?;
+    // encoding_rs only decodes UTF-16 and replacement, and encodes them as UTF-8.
+    if encoding.output_encoding() != encoding {
+        return Err(format!("csv encoding {} cannot be written", csv_config.encoding).into());
+    }
+    std::fs::create_dir_all(&csv_config.directory)?;
+
+    write_table::<data::Amateur>(
+        csv_config,
+        encoding,
+        data::Amateur::COLUMNS,
+        Some(amateur.len()),
+        amateur.iter().map(SqlRow::values),
+    )?;
+    write_table::<data::Entity>(
+        csv_config,
+        encoding,
+        data::Entity::COLUMNS,
+        Some(entity.len()),
+        entity.iter().map(SqlRow::values),
+    )?;
+    write_table::<data::ApplicationLicenseHeader>(
+        csv_config,
+        encoding,
+        data::ApplicationLicenseHeader::COLUMNS,
+        Some(application_license_header.len()),
+        application_license_header.iter().map(SqlRow::values),
+    )?;
+    write_table::<crate::License>(
+        csv_config,
+        encoding,
+        &profile.columns(),
+        None,
+        licenses.map(|license| profile.project(license.values())),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JoinMode;
+    use crate::fixtures;
+    use std::collections::HashMap;
+
+    fn transcoder(label: &str) -> Transcoder<Vec<u8>> {
+        Transcoder {
+            out: Vec::new(),
+            encoding: Encoding::for_label(label.as_bytes()).unwrap(),
+            pending: Vec::new(),
+            encoded: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn characters_split_across_writes_are_transcoded_whole() {
+        let mut windows_1252 = transcoder("windows-1252");
+        let text = "José €5 ☃".as_bytes();
+        // Every split point, including inside the two and three byte characters.
+        for split in 0..=text.len() {
+            windows_1252.write_all(&text[..split]).unwrap();
+            windows_1252.write_all(&text[split..]).unwrap();
+        }
+        let out = windows_1252.into_inner().unwrap();
+        assert_eq!(out, b"Jos\xe9 \x805 ?".repeat(text.len() + 1));
+
+        let mut utf_8 = transcoder("utf-8");
+        utf_8.write_all("☃".as_bytes()).unwrap();
+        assert_eq!(utf_8.into_inner().unwrap(), "☃".as_bytes());
+    }
+
+    fn csv_config(directory: &Path, delimiter: char, encoding: &str) -> CsvConfig {
+        CsvConfig {
+            directory: directory.to_string_lossy().into_owned(),
+            delimiter,
+            encoding: encoding.to_string(),
+        }
+    }
+
+    fn write(csv_config: &CsvConfig) -> Result<(), Box<dyn std::error::Error>> {
+        let en = fixtures::EN[0]
+            .replace("|First1|", "|José|")
+            .replace("|Last1|", "|Snow☃|")
+            .replace("|1 Main St|", "|1 Main St; Apt 2|");
+        let fcc_db = fixtures::parse_lines(fixtures::AM, &[&en], fixtures::HD);
+        let profile = Profile::named("callbook", &HashMap::new()).unwrap();
+        write_csv(
+            csv_config,
+            &fcc_db.amateur,
+            &fcc_db.entity,
+            &fcc_db.application_license_header,
+            &profile,
+            crate::iter_licenses(&fcc_db, &JoinMode::Inner),
+        )
+    }
+
+    #[test]
+    fn tables_have_a_header_row_and_the_configured_delimiter_and_encoding() {
+        let directory = tempfile::tempdir().unwrap();
+        write(&csv_config(directory.path(), ';', "windows-1252")).unwrap();
+
+        let entity = std::fs::read(directory.path().join("ham_EN.csv")).unwrap();
+        let mut lines = entity.split(|byte| *byte == b'\n');
+        let header: Vec<&str> = data::Entity::COLUMNS
+            .iter()
+            .map(|(_field, column)| *column)
+            .collect();
+        assert_eq!(lines.next().unwrap(), header.join(";").as_bytes());
+        let row = lines.next().unwrap();
+        assert!(row.starts_with(b"EN;1001;;;K1TBC;L;;;Jos\xe9;Q;Snow?;"));
+        assert!(row
+            .windows(20)
+            .any(|window| window == b";\"1 Main St; Apt 2\";"));
+
+        let licenses = std::fs::read(directory.path().join("ham_license.csv")).unwrap();
+        let mut lines = licenses.split(|byte| *byte == b'\n');
+        assert_eq!(
+            lines.next().unwrap(),
+            b"Call Sign;Entity Name;First Name;MI;Last Name;Suffix;Street Address;PO Box;City;\
+              State;Zip Code;Operator Class;License Status;Expired Date"
+        );
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with(b"K1TBC;;Jos\xe9;Q;Snow?;"));
+        assert!(!directory.path().join("ham_license.csv.tmp").exists());
+    }
+
+    #[test]
+    fn unusable_delimiters_and_encodings_are_rejected() {
+        let directory = tempfile::tempdir().unwrap();
+        for (delimiter, encoding) in [('→', "utf-8"), (',', "no-such"), (',', "utf-16le")] {
+            assert!(write(&csv_config(directory.path(), delimiter, encoding)).is_err());
+        }
+    }
+}
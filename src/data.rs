@@ -1,15 +1,28 @@
 use serde::{Deserialize, Serialize};
 use std::convert::From;
 
+/*
+ * The serde name of an enum variant, as the JSON output and --filter name it: "AmateurExtra",
+ * "Active".
+ */
+pub fn variant<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
 // Begin Enums
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub enum U64Null {
     Value(u64),
+    #[default]
     NULL,
 }
 
 // BEGIN AM
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub enum OperatorClass {
     Advanced,
     AmateurExtra,
@@ -17,6 +30,7 @@ pub enum OperatorClass {
     Novice,
     TechnicianPlus,
     Technician,
+    #[default]
     Unknown,
 }
 
@@ -33,6 +47,32 @@ impl From<&str> for OperatorClass {
         }
     }
 }
+
+// serde names of the operator classes in order of privilege, with their display names.
+const OPERATOR_CLASSES: &[(&str, &str)] = &[
+    ("Novice", "Novice"),
+    ("Technician", "Technician"),
+    ("TechnicianPlus", "Technician Plus"),
+    ("General", "General"),
+    ("Advanced", "Advanced"),
+    ("AmateurExtra", "Amateur Extra"),
+];
+
+// "Amateur Extra" for AmateurExtra, and so on.
+pub fn operator_class_name(operator_class: &str) -> &str {
+    OPERATOR_CLASSES
+        .iter()
+        .find(|(name, _display)| *name == operator_class)
+        .map(|(_name, display)| *display)
+        .unwrap_or(operator_class)
+}
+
+// Higher for more privileges; None for Unknown.
+pub fn operator_class_rank(operator_class: &str) -> Option<usize> {
+    OPERATOR_CLASSES
+        .iter()
+        .position(|(name, _display)| *name == operator_class)
+}
 // END AM
 
 // BEGIN EN
@@ -67,6 +107,7 @@ impl From<&str> for EntityType {
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Serialize, Deserialize, Clone)]
 pub enum ApplicantTypeCode {
     AmateurClub,
@@ -132,7 +173,7 @@ impl From<&str> for EnStatusCode {
 // END EN
 
 // BEGIN HD
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub enum LicenseStatus {
     Active,
     Cancelled,
@@ -141,6 +182,7 @@ pub enum LicenseStatus {
     ParentStationCanceled,
     Terminated,
     TermPending,
+    #[default]
     Unknown,
 }
 
@@ -159,12 +201,13 @@ impl From<&str> for LicenseStatus {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub enum DevelopmentalStaDemonstration {
     Developmental,
     Demonstration,
     Regular,
     SpecialTemporaryAuthority,
+    #[default]
     Unknown,
 }
 
@@ -182,7 +225,7 @@ impl From<&str> for DevelopmentalStaDemonstration {
 // END HD
 
 // AM.dat
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Amateur {
     pub record_type: String,
     pub unique_system_identifier: u32,
@@ -205,7 +248,7 @@ pub struct Amateur {
 }
 
 // EN.dat
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct Entity {
     pub record_type: String,
     pub unique_system_identifier: u32,
@@ -240,7 +283,7 @@ pub struct Entity {
 
 // HD.dat
 // Application License/Header
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct ApplicationLicenseHeader {
     pub record_type: String,
     pub unique_system_identifier: u32,
@@ -0,0 +1,469 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::config::JoinMode;
+use crate::data;
+use crate::{FccDB, License};
+
+/*
+ * Changes between two parsed copies of the database, keyed by unique system identifier.
+ *
+ * Either side may be a snapshot (write_dat) or a directory of extracted AM.dat, EN.dat and
+ * HD.dat files.  Licenses are compared through a small summary: new and removed licenses,
+ * cancellations and expirations, operator class upgrades and downgrades, and call sign,
+ * address and name changes.  `libfcc-rust diff OLD NEW` prints a report; with --json it prints
+ * the changes as JSON instead.
+ */
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    New,
+    Upgrade,
+    Downgrade,
+    CallsignChange,
+    NameChange,
+    AddressChange,
+    Cancelled,
+    Expired,
+    Removed,
+}
+
+impl ChangeKind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ChangeKind::New => "New licenses",
+            ChangeKind::Upgrade => "Upgrades",
+            ChangeKind::Downgrade => "Downgrades",
+            ChangeKind::CallsignChange => "Call sign changes",
+            ChangeKind::NameChange => "Name changes",
+            ChangeKind::AddressChange => "Address changes",
+            ChangeKind::Cancelled => "Cancelled",
+            ChangeKind::Expired => "Expired",
+            ChangeKind::Removed => "No longer in the database",
+        }
+    }
+}
+
+// What the diff looks at in a license.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct LicenseSummary {
+    pub unique_system_identifier: u32,
+    pub callsign: String,
+    pub frn: String,
+    pub name: String,
+    pub address: String,
+    pub city: String,
+    pub state: String,
+    pub zip_code: String,
+    pub operator_class: String,
+    pub license_status: String,
+    // MM/DD/YYYY from HD; missing from watchlist state saved by older versions.
+    #[serde(default)]
+    pub grant_date: String,
+}
+
+fn join_nonempty(parts: &[&str], separator: &str) -> String {
+    parts
+        .iter()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join(separator)
+}
+
+impl LicenseSummary {
+    pub fn new(license: &License) -> Self {
+        let mut summary = LicenseSummary {
+            unique_system_identifier: license.unique_system_identifier(),
+            callsign: license.callsign().to_string(),
+            frn: String::new(),
+            name: String::new(),
+            address: String::new(),
+            city: String::new(),
+            state: String::new(),
+            zip_code: String::new(),
+            operator_class: String::new(),
+            license_status: String::new(),
+            grant_date: String::new(),
+        };
+        if let Some(amateur) = &license.amateur {
+            summary.operator_class = data::variant(&amateur.operator_class);
+        }
+        if let Some(header) = &license.header {
+            summary.license_status = data::variant(&header.license_status);
+            summary.grant_date = header.grant_date.trim().to_string();
+        }
+        if let Some(licensee) = &license.licensee {
+            summary.frn = licensee.frn.clone();
+            summary.name = if licensee.last_name.trim().is_empty() {
+                licensee.entity_name.trim().to_string()
+            } else {
+                join_nonempty(
+                    &[
+                        &licensee.first_name,
+                        &licensee.mi,
+                        &licensee.last_name,
+                        &licensee.suffix,
+                    ],
+                    " ",
+                )
+            };
+            let street = if licensee.street_address.trim().is_empty()
+                && !licensee.po_box.trim().is_empty()
+            {
+                format!("PO Box {}", licensee.po_box.trim())
+            } else {
+                licensee.street_address.clone()
+            };
+            let region = join_nonempty(&[&licensee.state, &licensee.zip_code], " ");
+            summary.address = join_nonempty(&[&street, &licensee.city, &region], ", ");
+            summary.city = licensee.city.trim().to_string();
+            summary.state = licensee.state.trim().to_string();
+            summary.zip_code = licensee.zip_code.trim().to_string();
+        }
+        summary
+    }
+
+    pub fn operator_class_name(&self) -> &str {
+        data::operator_class_name(&self.operator_class)
+    }
+
+    fn rank(&self) -> Option<usize> {
+        data::operator_class_rank(&self.operator_class)
+    }
+
+    pub fn location(&self) -> String {
+        join_nonempty(&[&self.city, &self.state], " ")
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub unique_system_identifier: u32,
+    pub callsign: String,
+    pub name: String,
+    pub location: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+fn change(
+    kind: ChangeKind,
+    summary: &LicenseSummary,
+    from: Option<String>,
+    to: Option<String>,
+) -> Change {
+    Change {
+        kind,
+        unique_system_identifier: summary.unique_system_identifier,
+        callsign: summary.callsign.clone(),
+        name: summary.name.clone(),
+        location: summary.location(),
+        from,
+        to,
+    }
+}
+
+// What changed between two summaries of one license; either side may be missing.
+pub fn compare(old: Option<&LicenseSummary>, new: Option<&LicenseSummary>) -> Vec<Change> {
+    let (old, new) = match (old, new) {
+        (None, None) => return Vec::new(),
+        (None, Some(new)) => {
+            let class = Some(new.operator_class_name().to_string());
+            return vec![change(ChangeKind::New, new, None, class)];
+        }
+        (Some(old), None) => return vec![change(ChangeKind::Removed, old, None, None)],
+        (Some(old), Some(new)) => (old, new),
+    };
+
+    let mut changes = Vec::new();
+    if old.license_status != new.license_status {
+        let kind = match new.license_status.as_str() {
+            "Cancelled" => Some(ChangeKind::Cancelled),
+            "Expired" => Some(ChangeKind::Expired),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            let from = Some(old.license_status.clone());
+            let to = Some(new.license_status.clone());
+            changes.push(change(kind, new, from, to));
+        }
+    }
+    if let (Some(old_rank), Some(new_rank)) = (old.rank(), new.rank()) {
+        if old_rank != new_rank {
+            let kind = if new_rank > old_rank {
+                ChangeKind::Upgrade
+            } else {
+                ChangeKind::Downgrade
+            };
+            let from = Some(old.operator_class_name().to_string());
+            let to = Some(new.operator_class_name().to_string());
+            changes.push(change(kind, new, from, to));
+        }
+    }
+    for (kind, old_value, new_value) in [
+        (ChangeKind::CallsignChange, &old.callsign, &new.callsign),
+        (ChangeKind::NameChange, &old.name, &new.name),
+        (ChangeKind::AddressChange, &old.address, &new.address),
+    ] {
+        if !old_value.eq_ignore_ascii_case(new_value) {
+            let from = Some(old_value.clone());
+            let to = Some(new_value.clone());
+            changes.push(change(kind, new, from, to));
+        }
+    }
+    changes
+}
+
+pub fn summaries(licenses: impl Iterator<Item = License>) -> HashMap<u32, LicenseSummary> {
+    licenses
+        .map(|license| {
+            let summary = LicenseSummary::new(&license);
+            (summary.unique_system_identifier, summary)
+        })
+        .collect()
+}
+
+// Every change between two databases, grouped by kind and then by call sign.
+pub fn diff(old: &FccDB, new: &FccDB) -> Vec<Change> {
+    let old = summaries(crate::iter_licenses(old, &JoinMode::Left));
+    let new = summaries(crate::iter_licenses(new, &JoinMode::Left));
+    let mut changes: Vec<Change> = new
+        .iter()
+        .flat_map(|(unique_system_identifier, summary)| {
+            compare(old.get(unique_system_identifier), Some(summary))
+        })
+        .chain(
+            old.iter()
+                .filter(|(unique_system_identifier, _summary)| {
+                    !new.contains_key(unique_system_identifier)
+                })
+                .flat_map(|(_unique_system_identifier, summary)| compare(Some(summary), None)),
+        )
+        .collect();
+    changes.sort_by(|a, b| {
+        (a.kind, &a.callsign, a.unique_system_identifier).cmp(&(
+            b.kind,
+            &b.callsign,
+            b.unique_system_identifier,
+        ))
+    });
+    changes
+}
+
+#[derive(Serialize)]
+pub struct DiffReport {
+    pub old: String,
+    pub new: String,
+    pub summary: BTreeMap<ChangeKind, usize>,
+    pub changes: Vec<Change>,
+}
+
+pub fn report(old: &str, new: &str, changes: Vec<Change>) -> DiffReport {
+    let mut summary: BTreeMap<ChangeKind, usize> = BTreeMap::new();
+    for change in &changes {
+        *summary.entry(change.kind).or_default() += 1;
+    }
+    DiffReport {
+        old: old.to_string(),
+        new: new.to_string(),
+        summary,
+        changes,
+    }
+}
+
+// One line of the report, without the kind.
+pub fn describe(change: &Change) -> String {
+    let who = join_nonempty(&[&change.name, &change.location], ", ");
+    match (&change.from, &change.to) {
+        (Some(from), Some(to)) => format!("{:<10} {}: {} -> {}", change.callsign, who, from, to),
+        (None, Some(to)) => format!("{:<10} {}: {}", change.callsign, who, to),
+        _ => format!("{:<10} {}", change.callsign, who),
+    }
+}
+
+fn print_report(report: &DiffReport) {
+    println!("Changes from {} to {}", report.old, report.new);
+    if report.changes.is_empty() {
+        println!("\nNo changes.");
+    }
+    for (kind, count) in &report.summary {
+        println!("\n{} ({})", kind.title(), count);
+        for change in report.changes.iter().filter(|change| change.kind == *kind) {
+            println!("  {}", describe(change));
+        }
+    }
+}
+
+// diff [--json] OLD NEW
+pub fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--json").collect();
+    let [old_path, new_path] = paths[..] else {
+        return Err("usage: libfcc-rust diff [--json] OLD NEW".into());
+    };
+
+    let changes = diff(&FccDB::load(old_path)?, &FccDB::load(new_path)?);
+    let report = report(old_path, new_path, changes);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_report(&report);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    // The fixtures with K2TCD still active.
+    fn old() -> FccDB {
+        let hd = fixtures::HD[1].replacen("|K2TCD|E|", "|K2TCD|A|", 1);
+        fixtures::parse_lines(
+            fixtures::AM,
+            fixtures::EN,
+            &[fixtures::HD[0], &hd, fixtures::HD[2]],
+        )
+    }
+
+    /*
+     * K1TBC upgrades, moves, changes its name and is cancelled; K2TCD becomes K2NEW, downgrades
+     * and expires; K3TDE is gone, K4TEF is unchanged and K5NEW is new.
+     */
+    fn new() -> FccDB {
+        let en: Vec<String> = fixtures::EN[1..3]
+            .iter()
+            .map(|line| line.replace("K2TCD", "K2NEW"))
+            .collect();
+        fixtures::parse_lines(
+            &[
+                "AM|1001|||K1TBC|G|D|1||||||||||",
+                "AM|1002|||K2NEW|T|D|2||||||||K2TCD|G|",
+                "AM|1005|||K5NEW|E|D|||||||||||",
+            ],
+            &[
+                "EN|1001|||K1TBC|L|||First1|Q|Newname||||u1@example.com|9 Elm St|City1|WI|55401||||0000000001|I|||A|01/02/2020|",
+                &en[0],
+                &en[1],
+            ],
+            &[
+                "HD|1001|||K1TBC|C|HA|02/15/2021|02/15/2031|10/01/2026|||||||||||||||||||N||||||||||||||||1||||||||||||||",
+                fixtures::HD[1],
+                fixtures::HD[2],
+            ],
+        )
+    }
+
+    fn row(change: &Change) -> (ChangeKind, &str, Option<&str>, Option<&str>) {
+        (
+            change.kind,
+            change.callsign.as_str(),
+            change.from.as_deref(),
+            change.to.as_deref(),
+        )
+    }
+
+    #[test]
+    fn every_kind_of_change_is_found_in_order() {
+        let changes = diff(&old(), &new());
+        let rows: Vec<_> = changes.iter().map(row).collect();
+        assert_eq!(
+            rows,
+            [
+                (ChangeKind::New, "K5NEW", None, Some("Amateur Extra")),
+                (
+                    ChangeKind::Upgrade,
+                    "K1TBC",
+                    Some("Technician"),
+                    Some("General")
+                ),
+                (
+                    ChangeKind::Downgrade,
+                    "K2NEW",
+                    Some("General"),
+                    Some("Technician")
+                ),
+                (
+                    ChangeKind::CallsignChange,
+                    "K2NEW",
+                    Some("K2TCD"),
+                    Some("K2NEW")
+                ),
+                (
+                    ChangeKind::NameChange,
+                    "K1TBC",
+                    Some("First1 Q Last1"),
+                    Some("First1 Q Newname")
+                ),
+                (
+                    ChangeKind::AddressChange,
+                    "K1TBC",
+                    Some("1 Main St, City1, WI 55401"),
+                    Some("9 Elm St, City1, WI 55401")
+                ),
+                (
+                    ChangeKind::Cancelled,
+                    "K1TBC",
+                    Some("Active"),
+                    Some("Cancelled")
+                ),
+                (
+                    ChangeKind::Expired,
+                    "K2NEW",
+                    Some("Active"),
+                    Some("Expired")
+                ),
+                (ChangeKind::Removed, "K3TDE", None, None),
+            ]
+        );
+        assert!(diff(&old(), &old()).is_empty());
+    }
+
+    #[test]
+    fn compare_ignores_case_and_unranked_classes() {
+        let summary = LicenseSummary::new(&fixtures::license());
+        let mut other = summary.clone();
+        other.callsign = summary.callsign.to_lowercase();
+        other.operator_class = String::new();
+        assert!(compare(Some(&summary), Some(&other)).is_empty());
+        assert!(compare(None, None).is_empty());
+    }
+
+    #[test]
+    fn json_report_counts_each_kind() {
+        let report = report("old.dat", "new", diff(&old(), &new()));
+        let report = serde_json::to_value(&report).unwrap();
+        assert_eq!(report["old"], "old.dat");
+        assert_eq!(report["new"], "new");
+        assert_eq!(
+            report["summary"],
+            serde_json::json!({
+                "new": 1,
+                "upgrade": 1,
+                "downgrade": 1,
+                "callsign_change": 1,
+                "name_change": 1,
+                "address_change": 1,
+                "cancelled": 1,
+                "expired": 1,
+                "removed": 1,
+            })
+        );
+        assert_eq!(
+            report["changes"][0],
+            serde_json::json!({
+                "kind": "new",
+                "unique_system_identifier": 1005,
+                "callsign": "K5NEW",
+                "name": "",
+                "location": "",
+                "from": null,
+                "to": "Amateur Extra",
+            })
+        );
+    }
+}
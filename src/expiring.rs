@@ -0,0 +1,274 @@
+use chrono::{Duration, Local, NaiveDate};
+use serde::Serialize;
+
+use crate::config::{ExpiringConfig, JoinMode};
+use crate::diff::LicenseSummary;
+use crate::filter::Filter;
+use crate::{FccDB, License};
+
+/*
+ * Licenses to remind about renewing, for `libfcc-rust expiring [--json] [--as-of YYYY-MM-DD]
+ * [SNAPSHOT|DIRECTORY]`.
+ *
+ * A license is listed when its HD expired date falls within window_days from today (or the
+ * --as-of date), or has passed but is still inside the grace_days grace period, two years by
+ * default, in which it can still be renewed.  Cancelled and terminated licenses are left out.
+ * states, zip_codes (matched on their start) and the roster file of call signs narrow the list;
+ * each one given must match.  Each entry carries the licensee's mailing address from EN.
+ */
+
+#[derive(Serialize)]
+pub struct Expiring {
+    pub unique_system_identifier: u32,
+    pub callsign: String,
+    pub name: String,
+    pub operator_class: String,
+    pub license_status: String,
+    // YYYY-MM-DD.
+    pub expired_date: String,
+    // Negative once the license has expired.
+    pub days_left: i64,
+    pub in_grace_period: bool,
+    pub attention_line: String,
+    pub street_address: String,
+    pub po_box: String,
+    pub city: String,
+    pub state: String,
+    pub zip_code: String,
+}
+
+// One call sign per line; blank lines and # comments are skipped.
+fn read_roster(filename: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    Ok(std::fs::read_to_string(filename)?
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(str::to_ascii_uppercase)
+        .collect())
+}
+
+fn expiring(
+    config: &ExpiringConfig,
+    roster: &Option<Vec<String>>,
+    today: NaiveDate,
+    license: &License,
+) -> Option<Expiring> {
+    let header = license.header.as_ref()?;
+    let expired_date = NaiveDate::parse_from_str(header.expired_date.trim(), "%m/%d/%Y").ok()?;
+    let days_left = expired_date.signed_duration_since(today).num_days();
+    if days_left > config.window_days || days_left < -config.grace_days {
+        return None;
+    }
+    let summary = LicenseSummary::new(license);
+    if matches!(
+        summary.license_status.as_str(),
+        "Cancelled" | "Terminated" | "ParentStationCanceled"
+    ) {
+        return None;
+    }
+    if !config.states.is_empty()
+        && !config
+            .states
+            .iter()
+            .any(|state| state.eq_ignore_ascii_case(&summary.state))
+    {
+        return None;
+    }
+    if !config.zip_codes.is_empty()
+        && !config
+            .zip_codes
+            .iter()
+            .any(|zip_code| summary.zip_code.starts_with(zip_code.as_str()))
+    {
+        return None;
+    }
+    if let Some(roster) = roster {
+        if !roster.contains(&summary.callsign.to_ascii_uppercase()) {
+            return None;
+        }
+    }
+
+    let licensee = license.licensee.clone().unwrap_or_default();
+    Some(Expiring {
+        unique_system_identifier: summary.unique_system_identifier,
+        callsign: summary.callsign.clone(),
+        name: summary.name.clone(),
+        operator_class: summary.operator_class_name().to_string(),
+        license_status: summary.license_status.clone(),
+        expired_date: expired_date.format("%Y-%m-%d").to_string(),
+        days_left,
+        in_grace_period: days_left < 0,
+        attention_line: licensee.attention_line.trim().to_string(),
+        street_address: licensee.street_address.trim().to_string(),
+        po_box: licensee.po_box.trim().to_string(),
+        city: summary.city,
+        state: summary.state,
+        zip_code: summary.zip_code,
+    })
+}
+
+fn print_report(entries: &[Expiring], config: &ExpiringConfig, today: NaiveDate) {
+    println!(
+        "Licenses expiring by {} or expired since {}: {}",
+        today + Duration::days(config.window_days),
+        today - Duration::days(config.grace_days),
+        entries.len()
+    );
+    for entry in entries {
+        let when = match entry.days_left {
+            0 => "today".to_string(),
+            days if days > 0 => format!("in {} days", days),
+            days => format!("{} days ago, in grace period", -days),
+        };
+        println!(
+            "\n{:<10} {} {}  {}  {}",
+            entry.callsign, entry.expired_date, when, entry.operator_class, entry.name
+        );
+        let street = match (entry.street_address.as_str(), entry.po_box.as_str()) {
+            ("", po_box) if !po_box.is_empty() => format!("PO Box {}", po_box),
+            (street_address, _) => street_address.to_string(),
+        };
+        for line in [
+            entry.attention_line.clone(),
+            street,
+            format!("{}, {} {}", entry.city, entry.state, entry.zip_code),
+        ] {
+            if !line.trim_matches([',', ' ']).is_empty() {
+                println!("           {}", line);
+            }
+        }
+    }
+}
+
+// expiring [--json] [--as-of YYYY-MM-DD] [SNAPSHOT|DIRECTORY]
+pub fn run(
+    args: &[String],
+    default_path: &str,
+    config: &ExpiringConfig,
+    filter: &Filter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "usage: libfcc-rust expiring [--json] [--as-of YYYY-MM-DD] [SNAPSHOT|DIRECTORY]";
+    let mut json = false;
+    let mut today = Local::now().date_naive();
+    let mut paths: Vec<&String> = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--as-of" => {
+                let date = args.next().ok_or(usage)?;
+                today = NaiveDate::parse_from_str(date, "%Y-%m-%d")?;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let path = match paths[..] {
+        [] => default_path,
+        [path] => path.as_str(),
+        _ => return Err(usage.into()),
+    };
+    let roster = match &config.roster {
+        Some(filename) => Some(read_roster(filename)?),
+        None => None,
+    };
+
+    let fcc_db = FccDB::load(path)?;
+    let mut entries: Vec<Expiring> = crate::iter_licenses(&fcc_db, &JoinMode::Left)
+        .filter(|license| filter.matches(license))
+        .filter_map(|license| expiring(config, &roster, today, &license))
+        .collect();
+    entries.sort_by(|a, b| (&a.expired_date, &a.callsign).cmp(&(&b.expired_date, &b.callsign)));
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        print_report(&entries, config, today);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn license(callsign: &str) -> License {
+        fixtures::licenses(&JoinMode::Left)
+            .into_iter()
+            .find(|license| license.callsign() == callsign)
+            .unwrap()
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn config(window_days: i64, grace_days: i64) -> ExpiringConfig {
+        ExpiringConfig {
+            window_days,
+            grace_days,
+            ..ExpiringConfig::default()
+        }
+    }
+
+    #[test]
+    fn window_ends_on_its_last_day() {
+        // K1TBC expires 2031-02-15.
+        let license = license("K1TBC");
+        let config = config(30, 0);
+        let entry = expiring(&config, &None, date("2031-01-16"), &license).unwrap();
+        assert_eq!((entry.days_left, entry.in_grace_period), (30, false));
+        assert_eq!(entry.expired_date, "2031-02-15");
+        assert_eq!(entry.street_address, "1 Main St");
+        assert!(expiring(&config, &None, date("2031-01-15"), &license).is_none());
+    }
+
+    #[test]
+    fn grace_period_ends_on_its_last_day() {
+        // K2TCD expired 2022-03-15.
+        let license = license("K2TCD");
+        let config = config(0, 730);
+        let entry = expiring(&config, &None, date("2024-03-14"), &license).unwrap();
+        assert_eq!((entry.days_left, entry.in_grace_period), (-730, true));
+        assert!(expiring(&config, &None, date("2024-03-15"), &license).is_none());
+    }
+
+    #[test]
+    fn cancelled_licenses_are_left_out() {
+        // K4TEF expires 2024-05-15 but was cancelled the day after.
+        let license = license("K4TEF");
+        assert!(expiring(&config(30, 730), &None, date("2024-05-01"), &license).is_none());
+    }
+
+    #[test]
+    fn states_zip_codes_and_roster_narrow_the_list() {
+        let license = license("K1TBC");
+        let today = date("2031-02-01");
+        let listed = |config: &ExpiringConfig, roster: &Option<Vec<String>>| {
+            expiring(config, roster, today, &license).is_some()
+        };
+        let mut config = config(30, 0);
+        assert!(listed(&config, &None));
+
+        config.states = vec!["mn".to_string()];
+        assert!(!listed(&config, &None));
+        config.states.push("wi".to_string());
+        assert!(listed(&config, &None));
+
+        config.zip_codes = vec!["50".to_string()];
+        assert!(!listed(&config, &None));
+        config.zip_codes.push("554".to_string());
+        assert!(listed(&config, &None));
+
+        assert!(!listed(&config, &Some(vec!["K2TCD".to_string()])));
+        assert!(listed(&config, &Some(vec!["K1TBC".to_string()])));
+    }
+
+    #[test]
+    fn roster_skips_comments_and_blank_lines() {
+        let directory = tempfile::tempdir().unwrap();
+        let filename = directory.path().join("roster.txt");
+        std::fs::write(&filename, "# members\nk1tbc\n\n  K2TCD  # treasurer\n").unwrap();
+        let roster = read_roster(filename.to_str().unwrap()).unwrap();
+        assert_eq!(roster, ["K1TBC", "K2TCD"]);
+    }
+}
@@ -0,0 +1,449 @@
+use chrono::NaiveDate;
+use mysql::Value;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+use crate::data;
+use crate::sql::{self, ColumnType, SqlRow};
+
+/*
+ * Record filters over the joined license fields.
+ *
+ * A condition is `field op value`, with the field named as in the JSON license or ham_license
+ * (license_status, state, grant_date, ...) and op one of == != < <= > >= in, `not in`:
+ *
+ *     license_status == Active
+ *     state in [MN, WI]
+ *     operator_class >= General
+ *     grant_date > 2020-01-01
+ *
+ * Dates are compared as dates and written YYYY-MM-DD, operator classes by privilege, the
+ * identifier and U64Null fields as numbers, and everything else as case-insensitive text.  Enum
+ * fields take either the variant name or the ULS code, in any case, and reject anything else.
+ * A license passes when every condition holds; the outputs then keep only the AM, EN and HD
+ * records of passing licenses.
+ */
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    In,
+    NotIn,
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Integer,
+    Date,
+    OperatorClass,
+    Text,
+}
+
+#[derive(PartialEq, PartialOrd)]
+enum Key {
+    Integer(u64),
+    Date(NaiveDate),
+    Rank(u8),
+    Text(String),
+}
+
+struct Condition {
+    position: usize,
+    kind: Kind,
+    op: Op,
+    operands: Vec<Key>,
+}
+
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+fn kind(column: &str) -> Kind {
+    match sql::column_type(column) {
+        ColumnType::Integer | ColumnType::NullableInteger => Kind::Integer,
+        ColumnType::Text if column.ends_with(" Date") => Kind::Date,
+        ColumnType::Text if column.ends_with("Operator Class") => Kind::OperatorClass,
+        ColumnType::Text => Kind::Text,
+    }
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::NULL => String::new(),
+        Value::Bytes(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        Value::Int(value) => value.to_string(),
+        Value::UInt(value) => value.to_string(),
+        other => other.as_sql(true).trim_matches('\'').to_string(),
+    }
+}
+
+// The serde name and ULS code of an enum column's variant for a one-letter code.
+fn variant(column: &str, code: &str) -> Option<(String, Value)> {
+    match column {
+        "Operator Class" | "Previous Operator Class" => {
+            let variant = data::OperatorClass::from(code);
+            Some((data::variant(&variant), Value::from(variant)))
+        }
+        "License Status" => {
+            let variant = data::LicenseStatus::from(code);
+            Some((data::variant(&variant), Value::from(variant)))
+        }
+        "Developmental or STA or Demonstration" => {
+            let variant = data::DevelopmentalStaDemonstration::from(code);
+            Some((data::variant(&variant), Value::from(variant)))
+        }
+        _ => None,
+    }
+}
+
+// Every variant of an enum column as (name, code), found by trying each one-letter code.
+fn variants(column: &str) -> Option<Vec<(String, String)>> {
+    // Not an enum column.
+    variant(column, "")?;
+    Some(
+        ('A'..='Z')
+            .filter_map(|letter| {
+                let code = letter.to_string();
+                let (name, value) = variant(column, &code)?;
+                (text(&value) == code).then_some((name, code))
+            })
+            .collect(),
+    )
+}
+
+// The ULS code for an enum variant name or code in any case, or the operand unchanged.
+fn code(column: &str, operand: &str) -> Result<String, String> {
+    let Some(variants) = variants(column) else {
+        return Ok(operand.to_string());
+    };
+    if operand.is_empty() {
+        return Ok(String::new());
+    }
+    variants
+        .iter()
+        .find(|(name, code)| {
+            name.eq_ignore_ascii_case(operand) || code.eq_ignore_ascii_case(operand)
+        })
+        .map(|(_name, code)| code.clone())
+        .ok_or_else(|| {
+            let names: Vec<&str> = variants.iter().map(|(name, _code)| name.as_str()).collect();
+            format!(
+                "unknown value {:?}, expected one of {}",
+                operand,
+                names.join(", ")
+            )
+        })
+}
+
+fn rank(code: &str) -> Option<u8> {
+    match code.to_ascii_uppercase().as_str() {
+        "N" => Some(1),
+        "T" => Some(2),
+        "P" => Some(3),
+        "G" => Some(4),
+        "A" => Some(5),
+        "E" => Some(6),
+        _ => None,
+    }
+}
+
+// A record value as a key, or None when it is blank or malformed.
+fn key(kind: Kind, value: &str, date_format: &str) -> Option<Key> {
+    let value = value.trim();
+    match kind {
+        Kind::Integer => value.parse().ok().map(Key::Integer),
+        Kind::Date => NaiveDate::parse_from_str(value, date_format)
+            .ok()
+            .map(Key::Date),
+        Kind::OperatorClass => rank(value).map(Key::Rank),
+        Kind::Text => Some(Key::Text(value.to_uppercase())),
+    }
+}
+
+fn parse_op<'a>(text: &'a str) -> Option<(Op, &'a str)> {
+    let text = text.trim_start();
+    for (token, op) in [
+        ("==", Op::Eq),
+        ("!=", Op::Ne),
+        ("<=", Op::Le),
+        (">=", Op::Ge),
+        ("<", Op::Lt),
+        (">", Op::Gt),
+        ("=", Op::Eq),
+    ] {
+        if let Some(rest) = text.strip_prefix(token) {
+            return Some((op, rest));
+        }
+    }
+    // `in` and `not in` may run straight into the list.
+    let in_list = |text: &'a str| {
+        text.strip_prefix("in")
+            .filter(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '['))
+    };
+    if let Some(rest) = in_list(text) {
+        return Some((Op::In, rest));
+    }
+    text.strip_prefix("not")
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .and_then(|rest| in_list(rest.trim_start()))
+        .map(|rest| (Op::NotIn, rest))
+}
+
+fn unquote(operand: &str) -> &str {
+    let operand = operand.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = operand
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    operand
+}
+
+impl Condition {
+    fn parse(expression: &str) -> Result<Self, String> {
+        let expression = expression.trim();
+        let end = expression
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(expression.len());
+        let (field, rest) = expression.split_at(end);
+        let position = crate::License::COLUMNS
+            .iter()
+            .position(|(name, _column)| name.eq_ignore_ascii_case(field))
+            .ok_or(format!(
+                "unknown field {:?} in filter {:?}",
+                field, expression
+            ))?;
+        let column = crate::License::COLUMNS[position].1;
+        let kind = kind(column);
+        let (op, rest) =
+            parse_op(rest).ok_or(format!("missing operator in filter {:?}", expression))?;
+
+        let operands: Vec<&str> = match op {
+            Op::In | Op::NotIn => rest
+                .trim()
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+                .ok_or(format!("expected [a, b, ...] in filter {:?}", expression))?
+                .split(',')
+                .map(unquote)
+                .filter(|operand| !operand.is_empty())
+                .collect(),
+            _ => vec![unquote(rest)],
+        };
+        if matches!(kind, Kind::Text) && matches!(op, Op::Lt | Op::Le | Op::Gt | Op::Ge) {
+            return Err(format!(
+                "{} cannot be ordered in filter {:?}",
+                field, expression
+            ));
+        }
+        let operands = operands
+            .into_iter()
+            .map(|operand| {
+                let code = code(column, operand)
+                    .map_err(|why| format!("{} in filter {:?}", why, expression))?;
+                key(kind, &code, "%Y-%m-%d").ok_or(format!(
+                    "bad value {:?} in filter {:?}",
+                    operand, expression
+                ))
+            })
+            .collect::<Result<Vec<Key>, String>>()?;
+        Ok(Condition {
+            position,
+            kind,
+            op,
+            operands,
+        })
+    }
+
+    fn matches(&self, values: &[Value]) -> bool {
+        // A blank date, number or class only satisfies != and not in.
+        let Some(value) = key(self.kind, &text(&values[self.position]), "%m/%d/%Y") else {
+            return matches!(self.op, Op::Ne | Op::NotIn);
+        };
+        let ordering = self
+            .operands
+            .first()
+            .and_then(|operand| value.partial_cmp(operand));
+        match self.op {
+            Op::Eq => ordering == Some(Ordering::Equal),
+            Op::Ne => ordering != Some(Ordering::Equal),
+            Op::Lt => ordering == Some(Ordering::Less),
+            Op::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Op::Gt => ordering == Some(Ordering::Greater),
+            Op::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            Op::In => self.operands.contains(&value),
+            Op::NotIn => !self.operands.contains(&value),
+        }
+    }
+}
+
+impl Filter {
+    pub fn parse(expressions: &[String]) -> Result<Self, String> {
+        let conditions = expressions
+            .iter()
+            .map(|expression| Condition::parse(expression))
+            .collect::<Result<Vec<Condition>, String>>()?;
+        Ok(Filter { conditions })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    pub fn matches(&self, license: &crate::License) -> bool {
+        let values = license.values();
+        self.conditions
+            .iter()
+            .all(|condition| condition.matches(&values))
+    }
+
+    // The identifiers of the licenses that pass.
+    pub fn passing(&self, licenses: impl Iterator<Item = crate::License>) -> HashSet<u32> {
+        licenses
+            .filter(|license| self.matches(license))
+            .map(|license| license.unique_system_identifier())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JoinMode;
+    use crate::fixtures;
+
+    /*
+     * The fixture licenses, joined left:
+     *   1001 K1TBC  Technician     WI  Active   granted 02/15/2021
+     *   1002 K2TCD  General        MN  Expired  granted 03/15/2012
+     *   1003 K3TDE  Amateur Extra  IA  no HD row
+     *   1004 K4TEF  no AM or EN        Cancelled
+     */
+    fn passing(expressions: &[&str]) -> Vec<u32> {
+        let expressions: Vec<String> = expressions.iter().map(|e| e.to_string()).collect();
+        let filter = Filter::parse(&expressions).unwrap();
+        let mut passing: Vec<u32> = filter
+            .passing(fixtures::licenses(&JoinMode::Left).into_iter())
+            .into_iter()
+            .collect();
+        passing.sort();
+        passing
+    }
+
+    fn error(expression: &str) -> String {
+        match Filter::parse(&[expression.to_string()]) {
+            Ok(_) => panic!("{} parsed", expression),
+            Err(why) => why,
+        }
+    }
+
+    #[test]
+    fn empty_filter_passes_everything() {
+        assert!(Filter::parse(&[]).unwrap().is_empty());
+        assert_eq!(passing(&[]), [1001, 1002, 1003, 1004]);
+    }
+
+    #[test]
+    fn text_equality_ignores_case() {
+        assert_eq!(passing(&["state == wi"]), [1001]);
+        assert_eq!(passing(&["state = WI"]), [1001]);
+        assert_eq!(passing(&["callsign != k1tbc"]), [1002, 1003, 1004]);
+    }
+
+    #[test]
+    fn in_and_not_in() {
+        assert_eq!(passing(&["state in [MN, wi]"]), [1001, 1002]);
+        assert_eq!(passing(&["state in[MN]"]), [1002]);
+        assert_eq!(passing(&["state not in [MN, WI]"]), [1003, 1004]);
+        assert_eq!(passing(&["state not in[MN]"]), [1001, 1003, 1004]);
+        assert!(error("state in MN").contains("expected [a, b, ...]"));
+    }
+
+    #[test]
+    fn operands_may_be_quoted() {
+        assert_eq!(passing(&["city == \"City1\""]), [1001]);
+        assert_eq!(passing(&["state in ['MN', \"WI\"]"]), [1001, 1002]);
+        assert_eq!(passing(&["entity_name == 'Club Three'"]), [1003]);
+    }
+
+    #[test]
+    fn enum_fields_take_names_or_codes_in_any_case() {
+        assert_eq!(passing(&["license_status == Active"]), [1001]);
+        assert_eq!(passing(&["license_status == active"]), [1001]);
+        assert_eq!(passing(&["license_status == A"]), [1001]);
+        assert_eq!(
+            passing(&["license_status in [expired, CANCELLED]"]),
+            [1002, 1004]
+        );
+        assert_eq!(passing(&["operator_class == amateurextra"]), [1003]);
+        let why = error("license_status == Bogus");
+        assert!(why.contains("unknown value \"Bogus\""), "{}", why);
+        assert!(why.contains("Active"), "{}", why);
+    }
+
+    #[test]
+    fn dates_compare_as_dates() {
+        assert_eq!(passing(&["grant_date > 2020-01-01"]), [1001]);
+        assert_eq!(passing(&["grant_date <= 2012-03-15"]), [1002]);
+        assert_eq!(passing(&["grant_date == 2021-02-15"]), [1001]);
+        assert_eq!(
+            passing(&["expired_date >= 2022-03-15", "expired_date < 2031-02-15"]),
+            [1002, 1004]
+        );
+        assert!(error("grant_date > 02/15/2021").contains("bad value"));
+    }
+
+    #[test]
+    fn operator_classes_compare_by_privilege() {
+        assert_eq!(passing(&["operator_class >= General"]), [1002, 1003]);
+        assert_eq!(passing(&["operator_class < general"]), [1001]);
+        assert_eq!(passing(&["operator_class > G"]), [1003]);
+    }
+
+    #[test]
+    fn integers_compare_as_numbers() {
+        assert_eq!(passing(&["unique_system_identifier >= 1003"]), [1003, 1004]);
+        assert_eq!(passing(&["region_code == 2"]), [1002]);
+        assert!(error("unique_system_identifier > abc").contains("bad value"));
+    }
+
+    #[test]
+    fn blank_values_only_pass_negations() {
+        // 1003 has no HD row, and 1004 no AM row.
+        assert_eq!(passing(&["grant_date > 2000-01-01"]), [1001, 1002, 1004]);
+        assert_eq!(passing(&["grant_date != 2021-02-15"]), [1002, 1003, 1004]);
+        assert_eq!(
+            passing(&["grant_date not in [2021-02-15]"]),
+            [1002, 1003, 1004]
+        );
+        assert!(passing(&["operator_class < Novice"]).is_empty());
+        assert_eq!(
+            passing(&["operator_class != Technician"]),
+            [1002, 1003, 1004]
+        );
+    }
+
+    #[test]
+    fn conditions_must_all_hold() {
+        assert_eq!(
+            passing(&["state in [MN, WI]", "operator_class >= General"]),
+            [1002]
+        );
+    }
+
+    #[test]
+    fn malformed_filters_are_rejected() {
+        assert!(error("no_such_field == 1").contains("unknown field"));
+        assert!(error("state MN").contains("missing operator"));
+        assert!(error("state > MN").contains("cannot be ordered"));
+        assert!(error("state not MN").contains("missing operator"));
+    }
+}
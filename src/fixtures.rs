@@ -0,0 +1,55 @@
+use crate::config::JoinMode;
+use crate::{parse, FccDB, License};
+
+/*
+ * A few records in the ULS .dat format for the tests: three licenses, one with a contact, one
+ * without an HD row, and an HD row without AM or EN.
+ */
+pub const AM: &[&str] = &[
+    "AM|1001|||K1TBC|T|D|1||||||||||",
+    "AM|1002|||K2TCD|G|D|2||||||||N2OLD|T|",
+    "AM|1003|||K3TDE|E|D|||||||||||",
+];
+
+pub const EN: &[&str] = &[
+    "EN|1001|||K1TBC|L|||First1|Q|Last1||||u1@example.com|1 Main St|City1|WI|55401||||0000000001|I|||A|01/02/2020|",
+    "EN|1002|||K2TCD|L|||First2||Last2||||u2@example.com|2 Main St|City2|MN|55402||||0000000002|I|||A|01/02/2020|",
+    "EN|1002|||K2TCD|CL|||Contact2||Last2||||c2@example.com|2 Main St|City2|MN|55402||||0000000002|I|||A|01/02/2020|",
+    "EN|1003|||K3TDE|L||Club Three||||||||3 Main St|City3|IA|50003||||0000000003|B|||A|01/02/2020|",
+];
+
+pub const HD: &[&str] = &[
+    "HD|1001|||K1TBC|A|HA|02/15/2021|02/15/2031||||||||||||||||||||N||||||||||||||||1||||||||||||||",
+    "HD|1002|||K2TCD|E|HA|03/15/2012|03/15/2022||||||||||||||||||||N||||||||||||||||||||||||||||||",
+    "HD|1004|||K4TEF|C|HA|05/15/2014|05/15/2024|05/16/2024|||||||||||||||||||N||||||||||||||||||||||||||||||",
+];
+
+pub fn fcc_db() -> FccDB {
+    parse_lines(AM, EN, HD)
+}
+
+pub fn parse_lines(am: &[&str], en: &[&str], hd: &[&str]) -> FccDB {
+    FccDB {
+        amateur: am
+            .iter()
+            .map(|line| parse::parse_am_line(line.to_string()))
+            .collect(),
+        entity: en
+            .iter()
+            .map(|line| parse::parse_en_line(line.to_string()))
+            .collect(),
+        application_license_header: hd
+            .iter()
+            .map(|line| parse::parse_hd_line(line.to_string()))
+            .collect(),
+    }
+}
+
+pub fn licenses(join: &JoinMode) -> Vec<License> {
+    crate::iter_licenses(&fcc_db(), join).collect()
+}
+
+// The fully joined K1TBC.
+pub fn license() -> License {
+    licenses(&JoinMode::Inner).remove(0)
+}
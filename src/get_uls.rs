@@ -43,20 +43,21 @@ pub async fn download_ham_db() -> Result<(), Box<dyn std::error::Error>> {
         downloaded = file_size;
     } else {
         println!("Fresh file..");
-        std::fs::create_dir_all(parentdir).or(Err(format!("Failed to create file structure")))?;
+        std::fs::create_dir_all(parentdir)
+            .or(Err("Failed to create file structure".to_string()))?;
         file = File::create(path).or(Err(format!("Failed to create file '{}'", path.display())))?;
     }
 
     println!("Commencing transfer");
     while let Some(item) = stream.next().await {
-        let chunk = item.or(Err(format!("Error while downloading file")))?;
-        file.write(&chunk)
-            .or(Err(format!("Error while writing to file")))?;
+        let chunk = item.or(Err("Error while downloading file".to_string()))?;
+        file.write_all(&chunk)
+            .or(Err("Error while writing to file".to_string()))?;
         let new = min(downloaded + (chunk.len() as u64), total_size);
         downloaded = new;
         pb.set_position(new);
     }
 
     pb.finish_with_message(format!("Downloaded {} to {}", target, path.display()));
-    return Ok(());
+    Ok(())
 }
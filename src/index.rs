@@ -0,0 +1,525 @@
+use memmap2::Mmap;
+use mysql::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::sql::SqlRow;
+use crate::License;
+
+/*
+ * Memory-mapped license index.
+ *
+ * A compact, read-only file for lookups that start instantly: nothing is deserialized, a query
+ * only touches the pages it reads.  All integers are little-endian.
+ *
+ *   header          magic, version, field count, record count, string count, source
+ *                   timestamp string, bucket counts, then the u64 offset of each section
+ *   string offsets  string_count + 1 u32 byte offsets into the string bytes
+ *   string bytes    every distinct UTF-8 value once; string 0 is ""
+ *   records         record_count records of 1 + FIELDS.len() u32s: the unique system
+ *                   identifier, then a string number for each of FIELDS
+ *   callsign table  callsign_buckets u32 record numbers, EMPTY for an unused bucket
+ *   FRN table       frn_buckets u32 record numbers
+ *
+ * The hash tables use linear probing on FNV-1a of the key, so every record with a given key
+ * lies between its home bucket and the next empty one.
+ */
+const MAGIC: &[u8; 8] = b"FCCIDX\0\0";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 80;
+const EMPTY: u32 = u32::MAX;
+
+// The ham_license columns kept in the index, enough for a callbook entry.
+pub const FIELDS: &[&str] = &[
+    "Call Sign",
+    "Operator Class",
+    "License Status",
+    "Grant Date",
+    "Expired Date",
+    "Cancellation Date",
+    "Previous Call Sign",
+    "Trustee Call Sign",
+    "Entity Name",
+    "First Name",
+    "MI",
+    "Last Name",
+    "Suffix",
+    "Street Address",
+    "City",
+    "State",
+    "Zip Code",
+    "PO Box",
+    "FRN",
+    "Applicant Type Code",
+];
+
+struct Header {
+    record_count: u32,
+    string_count: u32,
+    source_timestamp: u32,
+    callsign_buckets: u32,
+    frn_buckets: u32,
+    string_offsets_at: u64,
+    string_bytes_at: u64,
+    records_at: u64,
+    callsign_table_at: u64,
+    frn_table_at: u64,
+}
+
+fn fnv1a(key: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in key.bytes() {
+        hash ^= u64::from(byte.to_ascii_uppercase());
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::NULL => String::new(),
+        Value::Bytes(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+        other => other.as_sql(true).trim_matches('\'').to_string(),
+    }
+}
+
+fn field_position(column: &str) -> Option<usize> {
+    FIELDS.iter().position(|field| *field == column)
+}
+
+// Twice as many buckets as keys, rounded up to a power of two.
+fn hash_table(keys: &[&str]) -> Vec<u32> {
+    let buckets = (keys.len() * 2).next_power_of_two().max(2);
+    let mut table = vec![EMPTY; buckets];
+    for (record, key) in keys.iter().enumerate() {
+        if key.is_empty() {
+            continue;
+        }
+        let mut bucket = fnv1a(key) as usize & (buckets - 1);
+        while table[bucket] != EMPTY {
+            bucket = (bucket + 1) & (buckets - 1);
+        }
+        table[bucket] = record as u32;
+    }
+    table
+}
+
+fn write_u32s(out: &mut impl Write, values: &[u32]) -> std::io::Result<()> {
+    for value in values {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub fn write_index(
+    filename: &str,
+    licenses: impl Iterator<Item = License>,
+    source_timestamp: Option<&str>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let positions: Vec<usize> = FIELDS
+        .iter()
+        .map(|field| {
+            License::COLUMNS
+                .iter()
+                .position(|(_field, column)| column == field)
+                .unwrap()
+        })
+        .collect();
+
+    let mut string_ids: HashMap<String, u32> = HashMap::new();
+    let mut strings: Vec<String> = Vec::new();
+    let mut intern = |value: String| -> u32 {
+        if let Some(id) = string_ids.get(&value) {
+            return *id;
+        }
+        let id = strings.len() as u32;
+        string_ids.insert(value.clone(), id);
+        strings.push(value);
+        id
+    };
+    intern(String::new());
+    let source_timestamp = match source_timestamp {
+        Some(timestamp) => intern(timestamp.to_string()),
+        None => EMPTY,
+    };
+
+    let mut records: Vec<u32> = Vec::new();
+    for license in licenses {
+        let values = license.values();
+        records.push(license.unique_system_identifier());
+        for position in &positions {
+            records.push(intern(text(&values[*position])));
+        }
+    }
+    let record_width = 1 + FIELDS.len();
+    let record_count = records.len() / record_width;
+
+    let key_column = |column: &str| -> Vec<&str> {
+        let field = 1 + field_position(column).unwrap();
+        records
+            .chunks(record_width)
+            .map(|record| strings[record[field] as usize].as_str())
+            .collect()
+    };
+    let callsign_table = hash_table(&key_column("Call Sign"));
+    let frn_table = hash_table(&key_column("FRN"));
+
+    let mut string_offsets: Vec<u32> = Vec::with_capacity(strings.len() + 1);
+    let mut string_bytes_len: usize = 0;
+    for string in &strings {
+        string_offsets.push(u32::try_from(string_bytes_len)?);
+        string_bytes_len += string.len();
+    }
+    string_offsets.push(u32::try_from(string_bytes_len)?);
+
+    // Sections after the string bytes start on a 4 byte boundary.
+    let string_offsets_at = HEADER_SIZE as u64;
+    let string_bytes_at = string_offsets_at + 4 * string_offsets.len() as u64;
+    let padding = (4 - string_bytes_len % 4) % 4;
+    let records_at = string_bytes_at + (string_bytes_len + padding) as u64;
+    let callsign_table_at = records_at + 4 * records.len() as u64;
+    let frn_table_at = callsign_table_at + 4 * callsign_table.len() as u64;
+
+    let tmp_filename = format!("{}.tmp", filename);
+    let mut out = BufWriter::new(File::create(&tmp_filename)?);
+    out.write_all(MAGIC)?;
+    write_u32s(
+        &mut out,
+        &[
+            VERSION,
+            FIELDS.len() as u32,
+            record_count as u32,
+            strings.len() as u32,
+            source_timestamp,
+            callsign_table.len() as u32,
+            frn_table.len() as u32,
+        ],
+    )?;
+    // Reserved, and keeps the offsets on an 8 byte boundary.
+    out.write_all(&0u32.to_le_bytes())?;
+    for offset in [
+        string_offsets_at,
+        string_bytes_at,
+        records_at,
+        callsign_table_at,
+        frn_table_at,
+    ] {
+        out.write_all(&offset.to_le_bytes())?;
+    }
+    write_u32s(&mut out, &string_offsets)?;
+    for string in &strings {
+        out.write_all(string.as_bytes())?;
+    }
+    out.write_all(&[0u8; 4][..padding])?;
+    write_u32s(&mut out, &records)?;
+    write_u32s(&mut out, &callsign_table)?;
+    write_u32s(&mut out, &frn_table)?;
+    out.into_inner()?.sync_all()?;
+    std::fs::rename(&tmp_filename, filename)?;
+    Ok(record_count)
+}
+
+pub struct LicenseIndex {
+    mmap: Mmap,
+    header: Header,
+}
+
+// One license in a LicenseIndex, read in place.
+pub struct IndexedLicense<'a> {
+    index: &'a LicenseIndex,
+    record: usize,
+}
+
+impl LicenseIndex {
+    pub fn open(filename: &str) -> Result<LicenseIndex, Box<dyn std::error::Error>> {
+        let file = File::open(filename)?;
+        // The index is only ever replaced by rename, never modified in place.
+        let mmap = unsafe { Mmap::map(&file)? };
+        if mmap.len() < HEADER_SIZE || &mmap[..8] != MAGIC {
+            return Err(format!("{} is not a license index", filename).into());
+        }
+        let u32_at = |at: usize| u32::from_le_bytes(mmap[at..at + 4].try_into().unwrap());
+        let u64_at = |at: usize| u64::from_le_bytes(mmap[at..at + 8].try_into().unwrap());
+        if u32_at(8) != VERSION || u32_at(12) as usize != FIELDS.len() {
+            return Err(format!("{} is an unsupported license index version", filename).into());
+        }
+        let header = Header {
+            record_count: u32_at(16),
+            string_count: u32_at(20),
+            source_timestamp: u32_at(24),
+            callsign_buckets: u32_at(28),
+            frn_buckets: u32_at(32),
+            string_offsets_at: u64_at(40),
+            string_bytes_at: u64_at(48),
+            records_at: u64_at(56),
+            callsign_table_at: u64_at(64),
+            frn_table_at: u64_at(72),
+        };
+
+        /*
+         * Every section is where the counts say it must be and the file ends with the FRN
+         * table, so no read past this point can leave the map.  Lookups mask with the bucket
+         * counts, which must be powers of two.
+         */
+        let truncated = || format!("{} is truncated", filename);
+        let corrupt = || format!("{} is corrupt", filename);
+        let len = mmap.len() as u64;
+        let string_offsets_at = HEADER_SIZE as u64;
+        let string_bytes_at = string_offsets_at + 4 * (u64::from(header.string_count) + 1);
+        if string_bytes_at > len {
+            return Err(truncated().into());
+        }
+        let offsets: Vec<u32> = (string_offsets_at as usize..string_bytes_at as usize)
+            .step_by(4)
+            .map(u32_at)
+            .collect();
+        if offsets[0] != 0 || offsets.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(corrupt().into());
+        }
+        let string_bytes_len = u64::from(offsets[offsets.len() - 1]);
+        let records_at = string_bytes_at + string_bytes_len.next_multiple_of(4);
+        let record_width = 1 + FIELDS.len() as u64;
+        let callsign_table_at = records_at + 4 * u64::from(header.record_count) * record_width;
+        let frn_table_at = callsign_table_at + 4 * u64::from(header.callsign_buckets);
+        let end = frn_table_at + 4 * u64::from(header.frn_buckets);
+        if end > len {
+            return Err(truncated().into());
+        }
+        if end != len
+            || header.string_offsets_at != string_offsets_at
+            || header.string_bytes_at != string_bytes_at
+            || header.records_at != records_at
+            || header.callsign_table_at != callsign_table_at
+            || header.frn_table_at != frn_table_at
+            || !header.callsign_buckets.is_power_of_two()
+            || !header.frn_buckets.is_power_of_two()
+        {
+            return Err(corrupt().into());
+        }
+        Ok(LicenseIndex { mmap, header })
+    }
+
+    fn u32_at(&self, at: u64) -> u32 {
+        let at = at as usize;
+        u32::from_le_bytes(self.mmap[at..at + 4].try_into().unwrap())
+    }
+
+    fn string(&self, id: u32) -> &str {
+        if id >= self.header.string_count {
+            return "";
+        }
+        let offsets = self.header.string_offsets_at + 4 * u64::from(id);
+        let start = self.header.string_bytes_at + u64::from(self.u32_at(offsets));
+        let end = self.header.string_bytes_at + u64::from(self.u32_at(offsets + 4));
+        self.mmap
+            .get(start as usize..end as usize)
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+            .unwrap_or("")
+    }
+
+    fn field(&self, record: usize, field: usize) -> u32 {
+        let width = 1 + FIELDS.len() as u64;
+        self.u32_at(self.header.records_at + 4 * (record as u64 * width + field as u64))
+    }
+
+    pub fn len(&self) -> usize {
+        self.header.record_count as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn source_timestamp(&self) -> Option<&str> {
+        match self.header.source_timestamp {
+            EMPTY => None,
+            id => Some(self.string(id)),
+        }
+    }
+
+    fn lookup(
+        &self,
+        table_at: u64,
+        buckets: u32,
+        column: &str,
+        key: &str,
+    ) -> Vec<IndexedLicense<'_>> {
+        let mut found = Vec::new();
+        if key.is_empty() || buckets == 0 {
+            return found;
+        }
+        let field = 1 + field_position(column).unwrap();
+        let mut bucket = fnv1a(key) & u64::from(buckets - 1);
+        // A table is never full when written, but a damaged one may be.
+        for _probe in 0..buckets {
+            let record = self.u32_at(table_at + 4 * bucket);
+            if record == EMPTY || record >= self.header.record_count {
+                break;
+            }
+            let record = record as usize;
+            if self
+                .string(self.field(record, field))
+                .eq_ignore_ascii_case(key)
+            {
+                found.push(IndexedLicense {
+                    index: self,
+                    record,
+                });
+            }
+            bucket = (bucket + 1) & u64::from(buckets - 1);
+        }
+        found
+    }
+
+    pub fn by_callsign(&self, callsign: &str) -> Vec<IndexedLicense<'_>> {
+        let header = &self.header;
+        self.lookup(
+            header.callsign_table_at,
+            header.callsign_buckets,
+            "Call Sign",
+            callsign,
+        )
+    }
+
+    pub fn by_frn(&self, frn: &str) -> Vec<IndexedLicense<'_>> {
+        let header = &self.header;
+        self.lookup(header.frn_table_at, header.frn_buckets, "FRN", frn)
+    }
+}
+
+impl<'a> IndexedLicense<'a> {
+    pub fn unique_system_identifier(&self) -> u32 {
+        self.index.field(self.record, 0)
+    }
+
+    fn value(&self, position: usize) -> &'a str {
+        self.index
+            .string(self.index.field(self.record, 1 + position))
+    }
+
+    // The value of one of FIELDS, or None for any other column.
+    pub fn get(&self, column: &str) -> Option<&'a str> {
+        field_position(column).map(|position| self.value(position))
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = (&'static str, &'a str)> + '_ {
+        FIELDS
+            .iter()
+            .enumerate()
+            .map(|(position, column)| (*column, self.value(position)))
+    }
+}
+
+// `lookup KEY...`: print every license with a matching call sign, or FRN for an all-digit key.
+pub fn lookup(filename: &str, keys: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let index = LicenseIndex::open(filename)?;
+    println!(
+        "{}: {} licenses ({})",
+        filename,
+        index.len(),
+        index.source_timestamp().unwrap_or("source date unknown")
+    );
+    for key in keys {
+        let found = if key.bytes().all(|byte| byte.is_ascii_digit()) {
+            index.by_frn(key)
+        } else {
+            index.by_callsign(key)
+        };
+        if found.is_empty() {
+            println!("\n{}: not found", key);
+        }
+        for license in found {
+            println!(
+                "\nUnique System Identifier: {}",
+                license.unique_system_identifier()
+            );
+            for (column, value) in license.fields() {
+                println!("{}: {}", column, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JoinMode;
+    use crate::fixtures;
+
+    fn write(directory: &tempfile::TempDir, licenses: Vec<License>) -> String {
+        let filename = directory.path().join("licenses.idx");
+        let filename = filename.to_string_lossy().into_owned();
+        write_index(&filename, licenses.into_iter(), Some("2026-10-18")).unwrap();
+        filename
+    }
+
+    #[test]
+    fn lookups_read_back_what_was_written() {
+        let directory = tempfile::tempdir().unwrap();
+        let filename = write(&directory, fixtures::licenses(&JoinMode::Left));
+        let index = LicenseIndex::open(&filename).unwrap();
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.source_timestamp(), Some("2026-10-18"));
+
+        let found = index.by_callsign("k2tcd");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].unique_system_identifier(), 1002);
+        assert_eq!(found[0].get("Previous Call Sign"), Some("N2OLD"));
+        assert_eq!(found[0].get("Last Name"), Some("Last2"));
+        assert_eq!(found[0].get("Email"), None);
+        assert_eq!(found[0].fields().count(), FIELDS.len());
+
+        let found = index.by_frn("0000000003");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].get("Call Sign"), Some("K3TDE"));
+
+        assert!(index.by_callsign("K9ZZZ").is_empty());
+        assert!(index.by_frn("0000000009").is_empty());
+        assert!(index.by_callsign("").is_empty());
+    }
+
+    #[test]
+    fn empty_index_opens() {
+        let directory = tempfile::tempdir().unwrap();
+        let index = LicenseIndex::open(&write(&directory, Vec::new())).unwrap();
+        assert!(index.is_empty());
+        assert!(index.by_callsign("K1TBC").is_empty());
+    }
+
+    #[test]
+    fn damaged_files_are_rejected() {
+        let directory = tempfile::tempdir().unwrap();
+        let filename = write(&directory, fixtures::licenses(&JoinMode::Left));
+        let bytes = std::fs::read(&filename).unwrap();
+
+        std::fs::write(&filename, &bytes[..bytes.len() - 4]).unwrap();
+        assert!(LicenseIndex::open(&filename).is_err());
+        std::fs::write(&filename, &bytes[..HEADER_SIZE + 4]).unwrap();
+        assert!(LicenseIndex::open(&filename).is_err());
+
+        // A string count past the string bytes.
+        let mut damaged = bytes.clone();
+        damaged[20..24].copy_from_slice(&u32::MAX.to_le_bytes());
+        std::fs::write(&filename, &damaged).unwrap();
+        assert!(LicenseIndex::open(&filename).is_err());
+
+        // A call sign table without an empty bucket still opens, and lookups end.
+        let mut full = bytes.clone();
+        let callsign_table_at = u64::from_le_bytes(full[64..72].try_into().unwrap()) as usize;
+        let frn_table_at = u64::from_le_bytes(full[72..80].try_into().unwrap()) as usize;
+        full[callsign_table_at..frn_table_at].fill(0);
+        std::fs::write(&filename, &full).unwrap();
+        let index = LicenseIndex::open(&filename).unwrap();
+        assert!(index.by_callsign("K9ZZZ").is_empty());
+        assert_eq!(index.by_callsign("K1TBC").len(), 8);
+
+        // Records moved over the call sign table.
+        let mut damaged = bytes;
+        let records_at = u64::from_le_bytes(damaged[56..64].try_into().unwrap());
+        damaged[56..64].copy_from_slice(&(records_at + 4).to_le_bytes());
+        std::fs::write(&filename, &damaged).unwrap();
+        assert!(LicenseIndex::open(&filename).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use serde::{Serialize, Serializer};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::config::JsonFormat;
+use crate::profile::Profile;
+use crate::License;
+
+/*
+ * JSON export of the joined licenses.
+ *
+ * Records are serialized one at a time straight into a buffered file, so memory use does not
+ * grow with the size of the export.  The array format produces the same pretty-printed output
+ * as serializing the whole vector at once.  The full profile writes each license nested as
+ * amateur, licensee, header and contacts; any other is a flat object of its fields.
+ */
+fn write_records<W: Write>(
+    out: &mut W,
+    format: &JsonFormat,
+    records: impl Iterator<Item = impl Serialize>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match format {
+        JsonFormat::Array => {
+            serde_json::Serializer::pretty(out).collect_seq(records)?;
+        }
+        JsonFormat::Ndjson => {
+            for record in records {
+                serde_json::to_writer(&mut *out, &record)?;
+                out.write_all(b"\n")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn write_licenses(
+    filename: &str,
+    format: &JsonFormat,
+    profile: &Profile,
+    licenses: impl Iterator<Item = License>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let tmp_filename = format!("{}.tmp", filename);
+    let mut out = BufWriter::new(File::create(&tmp_filename)?);
+    let mut count = 0;
+    let licenses = licenses.inspect(|_license| count += 1);
+
+    if profile.is_full() {
+        write_records(&mut out, format, licenses)?;
+    } else {
+        write_records(
+            &mut out,
+            format,
+            licenses.map(|license| profile.json(&license)),
+        )?;
+    }
+
+    out.into_inner()?.sync_all()?;
+    std::fs::rename(&tmp_filename, filename)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JoinMode;
+    use crate::fixtures;
+
+    fn round_trip(format: &JsonFormat) {
+        let licenses = fixtures::licenses(&JoinMode::Left);
+        let mut out: Vec<u8> = Vec::new();
+        write_records(&mut out, format, licenses.iter()).unwrap();
+
+        let read: Vec<License> = match format {
+            JsonFormat::Array => serde_json::from_slice(&out).unwrap(),
+            JsonFormat::Ndjson => out
+                .split(|byte| *byte == b'\n')
+                .filter(|line| !line.is_empty())
+                .map(|line| serde_json::from_slice(line).unwrap())
+                .collect(),
+        };
+        assert_eq!(
+            serde_json::to_value(&read).unwrap(),
+            serde_json::to_value(&licenses).unwrap()
+        );
+    }
+
+    #[test]
+    fn array_reads_back_into_licenses() {
+        round_trip(&JsonFormat::Array);
+    }
+
+    #[test]
+    fn ndjson_reads_back_into_licenses() {
+        round_trip(&JsonFormat::Ndjson);
+    }
+}
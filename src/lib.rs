@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+/*
+ * The parsed FCC amateur license database, for the libfcc-rust command and for programs that
+ * start from a snapshot: FccDB::load_snapshot reads one, iter_licenses joins its tables into
+ * License records, and columnar turns records into Arrow record batches.
+ */
+pub mod columnar;
+pub mod config;
+pub mod csv_export;
+pub mod data;
+pub mod diff;
+pub mod expiring;
+pub mod filter;
+#[cfg(test)]
+mod fixtures;
+pub mod get_uls;
+pub mod index;
+pub mod json;
+pub mod parse;
+pub mod pgsql;
+pub mod profile;
+pub mod redact;
+pub mod schema;
+pub mod snapshot;
+pub mod sql;
+pub mod sqldump;
+pub mod sqlite;
+pub mod stats;
+pub mod unzip_uls;
+pub mod watch;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FccDB {
+    pub amateur: Vec<data::Amateur>,
+    pub entity: Vec<data::Entity>,
+    pub application_license_header: Vec<data::ApplicationLicenseHeader>,
+}
+
+impl FccDB {
+    // Keeps only the records of these licenses.
+    pub fn retain(&mut self, keep: &HashSet<u32>) {
+        self.amateur
+            .retain(|record| keep.contains(&record.unique_system_identifier));
+        self.entity
+            .retain(|record| keep.contains(&record.unique_system_identifier));
+        self.application_license_header
+            .retain(|record| keep.contains(&record.unique_system_identifier));
+    }
+}
+
+/*
+ * One amateur license joined across AM, EN and HD.  The licensee is the EN row with entity type
+ * L; every other EN row for the license is in contacts.  With a left join any of amateur,
+ * licensee and header may be missing.
+ */
+#[derive(Serialize, Deserialize, Clone)]
+pub struct License {
+    pub amateur: Option<data::Amateur>,
+    pub licensee: Option<data::Entity>,
+    pub header: Option<data::ApplicationLicenseHeader>,
+    pub contacts: Vec<data::Entity>,
+}
+
+impl License {
+    // A license always has an AM or an HD row, and both carry the identifier and call sign.
+    pub fn unique_system_identifier(&self) -> u32 {
+        match (&self.amateur, &self.header) {
+            (Some(amateur), _) => amateur.unique_system_identifier,
+            (None, Some(header)) => header.unique_system_identifier,
+            (None, None) => 0,
+        }
+    }
+
+    pub fn callsign(&self) -> &str {
+        match (&self.amateur, &self.header) {
+            (Some(amateur), _) => &amateur.callsign,
+            (None, Some(header)) => &header.call_sign,
+            (None, None) => "",
+        }
+    }
+}
+
+// The records of a .dat file, and how many were dropped as duplicate keys.
+pub fn parse_am_file(filename: &str) -> (Vec<data::Amateur>, usize) {
+    let mut am_records: Vec<data::Amateur> = Vec::new();
+    if let Ok(lines) = read_lines(filename) {
+        for line in lines {
+            let Ok(line) = line else {
+                continue;
+            };
+            let am_record = parse::parse_am_line(line);
+            am_records.push(am_record);
+        }
+    }
+
+    parse::dedupe_records(am_records, |am| am.unique_system_identifier)
+}
+
+fn am_hashmap(data: &Vec<data::Amateur>) -> HashMap<u32, &data::Amateur> {
+    let mut am_records: HashMap<u32, &data::Amateur> = HashMap::new();
+    for record in data {
+        am_records.insert(record.unique_system_identifier, record);
+    }
+    am_records
+}
+
+pub fn parse_en_file(filename: &str) -> (Vec<data::Entity>, usize) {
+    let mut en_records: Vec<data::Entity> = Vec::new();
+    if let Ok(lines) = read_lines(filename) {
+        for line in lines {
+            let Ok(line) = line else {
+                continue;
+            };
+            let en_record = parse::parse_en_line(line);
+            en_records.push(en_record);
+        }
+    }
+
+    parse::dedupe_records(en_records, |en| {
+        (en.unique_system_identifier, en.entity_type.clone())
+    })
+}
+
+// Every EN row for a license, in file order: the licensee and any contacts.
+fn en_hashmap(data: &Vec<data::Entity>) -> HashMap<u32, Vec<&data::Entity>> {
+    let mut en_records: HashMap<u32, Vec<&data::Entity>> = HashMap::new();
+    for record in data {
+        en_records
+            .entry(record.unique_system_identifier)
+            .or_default()
+            .push(record);
+    }
+    en_records
+}
+
+pub fn parse_hd_file(filename: &str) -> (Vec<data::ApplicationLicenseHeader>, usize) {
+    let mut hd_records: Vec<data::ApplicationLicenseHeader> = Vec::new();
+    if let Ok(lines) = read_lines(filename) {
+        for line in lines {
+            let Ok(line) = line else {
+                continue;
+            };
+            let hd_record = parse::parse_hd_line(line);
+            hd_records.push(hd_record);
+        }
+    }
+    parse::dedupe_records(hd_records, |hd| hd.unique_system_identifier)
+}
+
+fn hd_hashmap(
+    data: &Vec<data::ApplicationLicenseHeader>,
+) -> HashMap<u32, &data::ApplicationLicenseHeader> {
+    let mut hd_records: HashMap<u32, &data::ApplicationLicenseHeader> = HashMap::new();
+    for record in data {
+        hd_records.insert(record.unique_system_identifier, record);
+    }
+    hd_records
+}
+
+/*
+ * One record per license, driven by AM, built as the iterator is consumed so writers can
+ * stream them.  An inner join drops licenses missing an AM, licensee or HD row; a left join
+ * keeps them, and also licenses only found in HD.
+ */
+pub fn iter_licenses<'a>(
+    orig_db: &'a FccDB,
+    join: &config::JoinMode,
+) -> impl Iterator<Item = License> + 'a {
+    let amateur_map = am_hashmap(&orig_db.amateur);
+    let entity_map = en_hashmap(&orig_db.entity);
+    let hd_map = hd_hashmap(&orig_db.application_license_header);
+    let join = join.clone();
+
+    let hd_only = orig_db
+        .application_license_header
+        .iter()
+        .filter(move |hd| !amateur_map.contains_key(&hd.unique_system_identifier))
+        .map(|hd| (hd.unique_system_identifier, None));
+    orig_db
+        .amateur
+        .iter()
+        .map(|am| (am.unique_system_identifier, Some(am)))
+        .chain(hd_only)
+        .filter_map(move |(unique_system_identifier, this_am)| {
+            let entities = entity_map
+                .get(&unique_system_identifier)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let licensee = entities.iter().position(|en| en.entity_type == "L");
+            let this_en = licensee.map(|i| entities[i]);
+            let this_hd = hd_map.get(&unique_system_identifier).copied();
+
+            if join == config::JoinMode::Inner
+                && (this_am.is_none() || this_en.is_none() || this_hd.is_none())
+            {
+                return None;
+            }
+            Some(License {
+                amateur: this_am.cloned(),
+                licensee: this_en.cloned(),
+                header: this_hd.cloned(),
+                contacts: entities
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _en)| Some(*i) != licensee)
+                    .map(|(_i, en)| (*en).clone())
+                    .collect(),
+            })
+        })
+}
+
+// The output is wrapped in a Result to allow matching on errors
+// Returns an Iterator to the Reader of the lines of the file.
+fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
+where
+    P: AsRef<Path>,
+{
+    let file = File::open(filename)?;
+    Ok(io::BufReader::new(file).lines())
+}
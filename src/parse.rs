@@ -1,4 +1,8 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
 use crate::data;
+use crate::sql::SqlRow;
 
 pub fn parse_am_line(line: String) -> data::Amateur {
     let split: Vec<&str> = line.split("|").collect();
@@ -14,13 +18,13 @@ pub fn parse_am_line(line: String) -> data::Amateur {
     let operator_class = data::OperatorClass::from(split[5]);
     data::Amateur {
         record_type: String::from(split[0]),
-        unique_system_identifier: unique_system_identifier,
+        unique_system_identifier,
         uls_file_num: String::from(split[2]),
         ebf_number: String::from(split[3]),
         callsign: String::from(split[4]),
-        operator_class: operator_class,
+        operator_class,
         group_code: String::from(split[6]),
-        region_code: region_code,
+        region_code,
         trustee_callsign: String::from(split[8]),
         trustee_indicator: String::from(split[9]),
         physician_certification: String::from(split[10]),
@@ -42,7 +46,7 @@ pub fn parse_en_line(line: String) -> data::Entity {
         .expect("Unique System Identifier is not a number!");
     data::Entity {
         record_type: String::from(split[0]),
-        unique_system_identifier: unique_system_identifier,
+        unique_system_identifier,
         uls_file_num: String::from(split[2]),
         ebf_number: String::from(split[3]),
         call_sign: String::from(split[4]),
@@ -89,11 +93,11 @@ pub fn parse_hd_line(line: String) -> data::ApplicationLicenseHeader {
     };
     data::ApplicationLicenseHeader {
         record_type: String::from(split[0]),
-        unique_system_identifier: unique_system_identifier,
+        unique_system_identifier,
         uls_file_num: String::from(split[2]),
         ebf_number: String::from(split[3]),
         call_sign: String::from(split[4]),
-        license_status: license_status,
+        license_status,
         radio_service_code: String::from(split[6]),
         grant_date: String::from(split[7]),
         expired_date: String::from(split[8]),
@@ -116,7 +120,7 @@ pub fn parse_hd_line(line: String) -> data::ApplicationLicenseHeader {
         mobile: String::from(split[25]),
         radiolocation: String::from(split[26]),
         satellite: String::from(split[27]),
-        developmental_or_sta: developmental_or_sta,
+        developmental_or_sta,
         interconnected_service: String::from(split[29]),
         certifier_first_name: String::from(split[30]),
         certifier_mi: String::from(split[31]),
@@ -132,7 +136,7 @@ pub fn parse_hd_line(line: String) -> data::ApplicationLicenseHeader {
         ethnicity: String::from(split[41]),
         effective_date: String::from(split[42]),
         last_action_date: String::from(split[43]),
-        auction_id: auction_id,
+        auction_id,
         reg_stat_broad_serv: String::from(split[45]),
         band_manager: String::from(split[46]),
         type_serv_broad_serv: String::from(split[47]),
@@ -149,3 +153,61 @@ pub fn parse_hd_line(line: String) -> data::ApplicationLicenseHeader {
         payment_cert_900: String::from(split[58]),
     }
 }
+
+/*
+ * The ULS files occasionally repeat a primary key.  Records are deduplicated as they are read,
+ * before any writer sees them, so every backend loads the same rows: the last record for each
+ * key wins, in the position it had in the file.  key gives a record's primary key, T::KEY.
+ * Returns the records and how many were dropped.
+ */
+pub fn dedupe_records<T: SqlRow, K: Hash + Eq>(
+    records: Vec<T>,
+    key: impl Fn(&T) -> K,
+) -> (Vec<T>, usize) {
+    let keys: Vec<K> = records.iter().map(key).collect();
+    let mut last: HashMap<&K, usize> = HashMap::new();
+    for (i, key) in keys.iter().enumerate() {
+        last.insert(key, i);
+    }
+    let dropped = records.len() - last.len();
+    if dropped == 0 {
+        return (records, 0);
+    }
+    eprintln!(
+        "Dropping {} {} rows with a duplicate key, keeping the last of each",
+        dropped,
+        T::TABLE
+    );
+    let records = records
+        .into_iter()
+        .zip(&keys)
+        .enumerate()
+        .filter(|(i, (_record, key))| last.get(key) == Some(i))
+        .map(|(_i, (record, _key))| record)
+        .collect();
+    (records, dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn dedupe_keeps_the_last_row_of_each_key() {
+        let mut entity = fixtures::fcc_db().entity;
+        let mut repeated = entity[0].clone();
+        repeated.email = "new@example.com".to_string();
+        entity.push(repeated);
+        let count = entity.len();
+
+        let (entity, dropped) = dedupe_records(entity, |en| {
+            (en.unique_system_identifier, en.entity_type.clone())
+        });
+        assert_eq!(dropped, 1);
+        assert_eq!(entity.len(), count - 1);
+        assert_eq!(entity.last().unwrap().email, "new@example.com");
+        // The contact shares the licensee's identifier but not its entity type.
+        assert!(entity.iter().any(|en| en.entity_type == "CL"));
+    }
+}
@@ -0,0 +1,230 @@
+use bytes::Bytes;
+use futures_util::{pin_mut, SinkExt};
+use indicatif::ProgressBar;
+use tokio_postgres::{NoTls, Transaction};
+
+use crate::sql::{self, ColumnType, SqlRow};
+
+/*
+ * PostgreSQL writer for the ham_* tables.
+ *
+ * Tables and columns are named exactly as in MySQL, Row Hash included, so both databases can
+ * be checked against each other row by row.  Each load is COPYed into <table>_staging and
+ * renamed over the live table inside one transaction, so readers see either the old or the new
+ * contents and a failed load leaves the live table untouched.  A live table from before Row
+ * Hash is replaced like any other by the first load.
+ */
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn index_name(table: &str, column: &str) -> String {
+    format!("{}_{}", table, column.to_lowercase().replace(' ', "_"))
+}
+
+async fn create_table<T: SqlRow>(
+    tx: &Transaction<'_>,
+    table: &str,
+) -> Result<(), tokio_postgres::Error> {
+    let mut definitions: Vec<String> = T::COLUMNS
+        .iter()
+        .map(|(_field, column)| {
+            let column_type = match sql::column_type(column) {
+                ColumnType::Integer => "BIGINT NOT NULL",
+                ColumnType::NullableInteger => "BIGINT",
+                ColumnType::Text => "TEXT NOT NULL",
+            };
+            format!("{} {}", quote(column), column_type)
+        })
+        .collect();
+    definitions.push(format!(
+        "{} CHAR(64) NOT NULL DEFAULT ''",
+        quote(sql::ROW_HASH_COLUMN)
+    ));
+    let key: Vec<String> = T::KEY.iter().map(|column| quote(column)).collect();
+    definitions.push(format!(
+        "CONSTRAINT {} PRIMARY KEY ({})",
+        quote(&format!("{}_pkey", table)),
+        key.join(", ")
+    ));
+    tx.batch_execute(&format!(
+        "CREATE TABLE IF NOT EXISTS {} ({})",
+        quote(table),
+        definitions.join(", ")
+    ))
+    .await?;
+
+    for column in T::INDEXES {
+        tx.batch_execute(&format!(
+            "CREATE INDEX IF NOT EXISTS {} ON {} ({})",
+            quote(&index_name(table, column)),
+            quote(table),
+            quote(column)
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+// Constraint and index names carry the table name, so they follow the staging table into place.
+async fn rename_table<T: SqlRow>(
+    tx: &Transaction<'_>,
+    from: &str,
+    to: &str,
+) -> Result<(), tokio_postgres::Error> {
+    tx.batch_execute(&format!(
+        "ALTER TABLE {} RENAME TO {}",
+        quote(from),
+        quote(to)
+    ))
+    .await?;
+    tx.batch_execute(&format!(
+        "ALTER TABLE {} RENAME CONSTRAINT {} TO {}",
+        quote(to),
+        quote(&format!("{}_pkey", from)),
+        quote(&format!("{}_pkey", to))
+    ))
+    .await?;
+    for column in T::INDEXES {
+        tx.batch_execute(&format!(
+            "ALTER INDEX {} RENAME TO {}",
+            quote(&index_name(from, column)),
+            quote(&index_name(to, column))
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+// Rows are sent to COPY in buffers of roughly this many bytes.
+const COPY_BUFFER_SIZE: usize = 1 << 20;
+
+pub async fn copy_rows<T: SqlRow>(
+    connection_string: &str,
+    records: &[T],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut client, connection) = tokio_postgres::connect(connection_string, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(why) = connection.await {
+            println!("PostgreSQL connection error: {}", why);
+        }
+    });
+    let tx = client.transaction().await?;
+    let staging = format!("{}_staging", T::TABLE);
+    let old = format!("{}_old", T::TABLE);
+
+    create_table::<T>(&tx, T::TABLE).await?;
+    tx.batch_execute(&format!("DROP TABLE IF EXISTS {}", quote(&staging)))
+        .await?;
+    create_table::<T>(&tx, &staging).await?;
+
+    println!("Copying rows into {}", staging);
+    let columns: Vec<String> = T::COLUMNS
+        .iter()
+        .map(|(_field, column)| *column)
+        .chain(std::iter::once(sql::ROW_HASH_COLUMN))
+        .map(quote)
+        .collect();
+    let pb = ProgressBar::new(records.len().try_into().unwrap());
+    let sink = tx
+        .copy_in(&format!(
+            "COPY {} ({}) FROM STDIN",
+            quote(&staging),
+            columns.join(", ")
+        ))
+        .await?;
+    pin_mut!(sink);
+    let mut buffer: Vec<u8> = Vec::with_capacity(COPY_BUFFER_SIZE);
+    for record in records {
+        sql::write_text_row(&mut buffer, &sql::hashed_values(record));
+        pb.inc(1);
+        if buffer.len() >= COPY_BUFFER_SIZE {
+            sink.send(Bytes::from(std::mem::take(&mut buffer))).await?;
+        }
+    }
+    if !buffer.is_empty() {
+        sink.send(Bytes::from(buffer)).await?;
+    }
+    sink.finish().await?;
+    pb.finish();
+
+    println!("Swapping {} into place", staging);
+    tx.batch_execute(&format!("DROP TABLE IF EXISTS {}", quote(&old)))
+        .await?;
+    rename_table::<T>(&tx, T::TABLE, &old).await?;
+    rename_table::<T>(&tx, &staging, T::TABLE).await?;
+    tx.batch_execute(&format!("DROP TABLE {}", quote(&old)))
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    /*
+     * Needs a scratch PostgreSQL database whose ham_AM it may replace, e.g.
+     * FCC_TEST_POSTGRES="host=/tmp user=fcc dbname=fcc_test" cargo test -- --ignored
+     */
+    #[tokio::test]
+    #[ignore]
+    async fn second_copy_replaces_the_first() {
+        let connection_string = std::env::var("FCC_TEST_POSTGRES")
+            .expect("FCC_TEST_POSTGRES is the connection string of a scratch database");
+        let amateur = fixtures::fcc_db().amateur;
+        copy_rows(&connection_string, &amateur).await.unwrap();
+        copy_rows(&connection_string, &amateur[1..]).await.unwrap();
+
+        let (client, connection) = tokio_postgres::connect(&connection_string, NoTls)
+            .await
+            .unwrap();
+        tokio::spawn(connection);
+        let rows = client
+            .query(
+                "SELECT \"Unique System Identifier\", \"Call Sign\", \"Row Hash\" \
+                 FROM \"ham_AM\" ORDER BY 1",
+                &[],
+            )
+            .await
+            .unwrap();
+        let loaded: Vec<(i64, String, String)> = rows
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect();
+        let expected: Vec<(i64, String, String)> = amateur[1..]
+            .iter()
+            .map(|record| {
+                let hash = match sql::hashed_values(record).pop() {
+                    Some(mysql::Value::Bytes(bytes)) => String::from_utf8(bytes).unwrap(),
+                    _ => String::new(),
+                };
+                let identifier = i64::from(record.unique_system_identifier);
+                (identifier, record.callsign.clone(), hash)
+            })
+            .collect();
+        assert_eq!(loaded, expected);
+
+        // The staging table took the live table's name, constraint and indexes.
+        let tables = client
+            .query(
+                "SELECT tablename FROM pg_tables WHERE tablename LIKE 'ham\\_AM%' ORDER BY 1",
+                &[],
+            )
+            .await
+            .unwrap();
+        let tables: Vec<String> = tables.iter().map(|row| row.get(0)).collect();
+        assert_eq!(tables, ["ham_AM"]);
+        let indexes = client
+            .query(
+                "SELECT indexname FROM pg_indexes WHERE tablename = 'ham_AM' ORDER BY 1",
+                &[],
+            )
+            .await
+            .unwrap();
+        let indexes: Vec<String> = indexes.iter().map(|row| row.get(0)).collect();
+        assert_eq!(indexes, ["ham_AM_call_sign", "ham_AM_pkey"]);
+    }
+}
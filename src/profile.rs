@@ -0,0 +1,239 @@
+use mysql::Value;
+use serde::{Serialize, Serializer};
+use std::collections::HashMap;
+
+use crate::sql::SqlRow;
+
+/*
+ * Output profiles: which joined license fields the JSON, CSV and SQL dump writers include, and
+ * in what order.
+ *
+ * `full` is every field, with JSON keeping the nested amateur/licensee/header/contacts form.
+ * `callbook` is the call sign, name, mailing address, class and expiry.  Other profiles are
+ * lists of field names in config, named as in ham_license's COLUMNS; any other profile makes
+ * each JSON license a flat object of just those fields, with the values the full profile has.
+ */
+
+const CALLBOOK: &[&str] = &[
+    "callsign",
+    "entity_name",
+    "first_name",
+    "mi",
+    "last_name",
+    "suffix",
+    "street_address",
+    "po_box",
+    "city",
+    "state",
+    "zip_code",
+    "operator_class",
+    "license_status",
+    "expired_date",
+];
+
+pub struct Profile {
+    positions: Vec<usize>,
+}
+
+impl Profile {
+    pub fn full() -> Self {
+        Profile {
+            positions: (0..crate::License::COLUMNS.len()).collect(),
+        }
+    }
+
+    pub fn from_fields(fields: &[&str]) -> Result<Self, String> {
+        let positions = fields
+            .iter()
+            .map(|field| {
+                crate::License::COLUMNS
+                    .iter()
+                    .position(|(name, _column)| name == field)
+                    .ok_or(format!("unknown field {:?} in output profile", field))
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
+        if positions.is_empty() {
+            return Err("output profile has no fields".to_string());
+        }
+        Ok(Profile { positions })
+    }
+
+    // A built-in profile, or one defined under profiles in config.
+    pub fn named(name: &str, profiles: &HashMap<String, Vec<String>>) -> Result<Self, String> {
+        match (profiles.get(name), name) {
+            (Some(fields), _) => {
+                Self::from_fields(&fields.iter().map(String::as_str).collect::<Vec<&str>>())
+            }
+            (None, "full") => Ok(Self::full()),
+            (None, "callbook") => Self::from_fields(CALLBOOK),
+            (None, _) => Err(format!("unknown output profile {}", name)),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.positions.len() == crate::License::COLUMNS.len()
+            && self
+                .positions
+                .iter()
+                .enumerate()
+                .all(|(i, position)| i == *position)
+    }
+
+    // (field, column) pairs, as in SqlRow::COLUMNS.
+    pub fn columns(&self) -> Vec<(&'static str, &'static str)> {
+        self.positions
+            .iter()
+            .map(|position| crate::License::COLUMNS[*position])
+            .collect()
+    }
+
+    // This profile, led by the identifier if it lacks one, for a ham_license table with its key.
+    pub fn keyed(&self) -> Self {
+        let mut positions = self.positions.clone();
+        if !positions.contains(&0) {
+            positions.insert(0, 0);
+        }
+        Profile { positions }
+    }
+
+    pub fn project(&self, values: Vec<Value>) -> Vec<Value> {
+        self.positions
+            .iter()
+            .map(|position| values[*position].clone())
+            .collect()
+    }
+
+    /*
+     * A flat JSON object of the profile's fields, serialized as in the full profile, so coded
+     * fields are named: "Technician", not "T".  A field shared by several tables, such as the
+     * identifier, comes from the first of AM, EN and HD the license has; fields of a missing
+     * table are null.
+     */
+    pub fn json(&self, license: &crate::License) -> Fields {
+        let tables: Vec<serde_json::Map<String, serde_json::Value>> = [
+            serde_json::to_value(&license.amateur),
+            serde_json::to_value(&license.licensee),
+            serde_json::to_value(&license.header),
+        ]
+        .into_iter()
+        .filter_map(|table| match table {
+            Ok(serde_json::Value::Object(fields)) => Some(fields),
+            _ => None,
+        })
+        .collect();
+        let fields = self
+            .columns()
+            .into_iter()
+            .map(|(field, _column)| {
+                let value = tables.iter().find_map(|table| table.get(field)).cloned();
+                (field, value.unwrap_or_default())
+            })
+            .collect();
+        Fields(fields)
+    }
+}
+
+// Serializes as a map in profile order; serde_json::Map would sort the keys.
+pub struct Fields(Vec<(&'static str, serde_json::Value)>);
+
+impl Serialize for Fields {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(field, value)| (field, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JoinMode;
+    use crate::fixtures;
+
+    fn fields(profile: &Profile) -> Vec<&'static str> {
+        profile
+            .columns()
+            .into_iter()
+            .map(|(field, _column)| field)
+            .collect()
+    }
+
+    #[test]
+    fn named_profiles() {
+        let mut profiles = HashMap::new();
+        assert!(Profile::named("full", &profiles).unwrap().is_full());
+        assert_eq!(
+            fields(&Profile::named("callbook", &profiles).unwrap()),
+            CALLBOOK
+        );
+        assert!(Profile::named("roster", &profiles).is_err());
+
+        // Profiles in config come first, even over a built-in name.
+        profiles.insert("callbook".to_string(), vec!["frn".to_string()]);
+        profiles.insert("roster".to_string(), vec!["callsign".to_string()]);
+        assert_eq!(
+            fields(&Profile::named("callbook", &profiles).unwrap()),
+            ["frn"]
+        );
+        assert_eq!(
+            fields(&Profile::named("roster", &profiles).unwrap()),
+            ["callsign"]
+        );
+    }
+
+    #[test]
+    fn from_fields_checks_the_names() {
+        let profile = Profile::from_fields(&["state", "callsign"]).unwrap();
+        assert_eq!(
+            profile.columns(),
+            [("state", "State"), ("callsign", "Call Sign")]
+        );
+        assert!(Profile::from_fields(&["callsign", "call_sign"]).is_err());
+        assert!(Profile::from_fields(&[]).is_err());
+    }
+
+    #[test]
+    fn only_every_field_in_order_is_full() {
+        let all: Vec<&str> = crate::License::COLUMNS
+            .iter()
+            .map(|(field, _column)| *field)
+            .collect();
+        assert!(Profile::from_fields(&all).unwrap().is_full());
+        assert!(!Profile::from_fields(&all[1..]).unwrap().is_full());
+        let mut reordered = all.clone();
+        reordered.swap(0, 1);
+        assert!(!Profile::from_fields(&reordered).unwrap().is_full());
+    }
+
+    #[test]
+    fn project_and_keyed_follow_the_profile_order() {
+        let license = fixtures::license();
+        let profile = Profile::from_fields(&["zip_code", "callsign"]).unwrap();
+        assert_eq!(
+            profile.project(license.values()),
+            [Value::from("55401"), Value::from("K1TBC")]
+        );
+        let keyed = profile.keyed();
+        assert_eq!(
+            fields(&keyed),
+            ["unique_system_identifier", "zip_code", "callsign"]
+        );
+        assert_eq!(fields(&keyed.keyed()), fields(&keyed));
+    }
+
+    #[test]
+    fn json_names_coded_fields_like_the_full_profile() {
+        let profile = Profile::named("callbook", &HashMap::new()).unwrap();
+        let licenses = fixtures::licenses(&JoinMode::Left);
+        let json = serde_json::to_string(&profile.json(&licenses[0])).unwrap();
+        assert!(json.starts_with(r#"{"callsign":"K1TBC","entity_name":"","first_name":"First1""#));
+        assert!(json.ends_with(
+            r#""operator_class":"Technician","license_status":"Active","expired_date":"02/15/2031"}"#
+        ));
+
+        // K4TEF has only an HD row.
+        let profile = Profile::from_fields(&["unique_system_identifier", "state"]).unwrap();
+        assert_eq!(
+            serde_json::to_value(profile.json(&licenses[3])).unwrap(),
+            serde_json::json!({"unique_system_identifier": 1004, "state": null})
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use hmac::{Hmac, KeyInit, Mac};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::config::{RedactAction, RedactionConfig};
+use crate::data;
+use crate::schema;
+use crate::sql::SqlRow;
+
+/*
+ * PII redaction of the parsed records, before anything is written.
+ *
+ * Each configured field is dropped (left blank), masked (all but its last four characters
+ * become '*', or all of a value that short) or replaced by a keyed hash: the first 16 hex digits
+ * of its HMAC-SHA256 under the key in key_file.  Only fields whose column holds 16 characters
+ * can be hashed, so FRN, Call Sign, Zip Code and the one-letter fields cannot.  With the same key a value always hashes the same, so hashed fields still
+ * join across weekly exports; keep the key private, since anyone holding it can test guesses.
+ * Fields are named as in the JSON records and apply to whichever of AM, EN and HD have them;
+ * callsign and call_sign are the same field.  Blank values stay blank.
+ */
+
+// Hex digits kept from the HMAC.
+const HASH_LENGTH: usize = 16;
+
+// Each table's fields to redact, by their name in that table.
+pub struct Redactor {
+    amateur: Vec<(&'static str, RedactAction)>,
+    entity: Vec<(&'static str, RedactAction)>,
+    header: Vec<(&'static str, RedactAction)>,
+    key: Vec<u8>,
+}
+
+// Whether the field is a string in T, so a redacted value can be stored back in it.
+fn is_text_field<T: SqlRow + Serialize + DeserializeOwned + Default>(field: &str) -> bool {
+    let mut record = match serde_json::to_value(T::default()) {
+        Ok(serde_json::Value::Object(record)) => record,
+        _ => return false,
+    };
+    match record.get_mut(field) {
+        Some(value @ serde_json::Value::String(_)) => {
+            *value = serde_json::Value::from("*");
+            serde_json::from_value::<T>(serde_json::Value::Object(record)).is_ok()
+        }
+        _ => false,
+    }
+}
+
+// The SQL column of a field in any of the tables.
+fn column(field: &str) -> Option<&'static str> {
+    [
+        data::Amateur::COLUMNS,
+        data::Entity::COLUMNS,
+        data::ApplicationLicenseHeader::COLUMNS,
+    ]
+    .iter()
+    .flat_map(|columns| columns.iter())
+    .find(|(name, _column)| *name == field)
+    .map(|(_name, column)| *column)
+}
+
+/*
+ * T's fields for the configured ones.  Fields are matched through their SQL column, so the
+ * shared columns apply across tables whatever they are called: callsign in AM and call_sign in
+ * EN and HD are both the Call Sign.
+ */
+fn table_fields<T: SqlRow + Serialize + DeserializeOwned + Default>(
+    fields: &[(&'static str, RedactAction)],
+) -> Result<Vec<(&'static str, RedactAction)>, Box<dyn std::error::Error>> {
+    let mut table_fields = Vec::new();
+    for (column, action) in fields {
+        let Some((field, _column)) = T::COLUMNS.iter().find(|(_field, c)| c == column) else {
+            continue;
+        };
+        // Coded fields cannot hold a redacted value.
+        if !is_text_field::<T>(field) {
+            return Err(format!("field {} cannot be redacted", field).into());
+        }
+        table_fields.push((*field, action.clone()));
+    }
+    Ok(table_fields)
+}
+
+fn mask(value: &str) -> String {
+    let length = value.chars().count();
+    value
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if i + 4 < length || length <= 4 {
+                '*'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+impl Redactor {
+    pub fn new(config: &RedactionConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut columns: Vec<(&'static str, RedactAction)> = Vec::new();
+        for (field, action) in &config.fields {
+            let column = column(field).ok_or(format!("unknown field {} in redaction", field))?;
+            // The identifier joins the tables.
+            if column == "Unique System Identifier" {
+                return Err(format!("field {} cannot be redacted", field).into());
+            }
+            if columns.iter().any(|(c, _action)| *c == column) {
+                return Err(format!("field {} is configured twice in redaction", field).into());
+            }
+            if *action == RedactAction::Hash
+                && schema::column_width(column).is_some_and(|width| width < HASH_LENGTH)
+            {
+                return Err(
+                    format!("the column of field {} is too narrow for a hash", field).into(),
+                );
+            }
+            columns.push((column, action.clone()));
+        }
+        columns.sort_by(|a, b| a.0.cmp(b.0));
+
+        let key = match &config.key_file {
+            Some(key_file) => std::fs::read(key_file)?.trim_ascii().to_vec(),
+            None => Vec::new(),
+        };
+        if key.is_empty()
+            && columns
+                .iter()
+                .any(|(_column, action)| *action == RedactAction::Hash)
+        {
+            return Err("redaction hashes fields but key_file is missing or empty".into());
+        }
+        Ok(Redactor {
+            amateur: table_fields::<data::Amateur>(&columns)?,
+            entity: table_fields::<data::Entity>(&columns)?,
+            header: table_fields::<data::ApplicationLicenseHeader>(&columns)?,
+            key,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amateur.is_empty() && self.entity.is_empty() && self.header.is_empty()
+    }
+
+    fn hash(&self, value: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).unwrap();
+        mac.update(value.as_bytes());
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()[..HASH_LENGTH]
+            .to_string()
+    }
+
+    fn redact_value(&self, action: &RedactAction, value: &str) -> String {
+        if value.is_empty() {
+            return String::new();
+        }
+        match action {
+            RedactAction::Drop => String::new(),
+            RedactAction::Mask => mask(value),
+            RedactAction::Hash => self.hash(value),
+        }
+    }
+
+    fn redact<T: Serialize + DeserializeOwned>(
+        &self,
+        fields: &[(&'static str, RedactAction)],
+        record: T,
+    ) -> T {
+        let mut value = serde_json::to_value(record).unwrap();
+        if let serde_json::Value::Object(values) = &mut value {
+            for (field, action) in fields {
+                if let Some(serde_json::Value::String(text)) = values.get_mut(*field) {
+                    *text = self.redact_value(action, text);
+                }
+            }
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    // Tables without a redacted field are passed through untouched.
+    fn redact_all<T: Serialize + DeserializeOwned>(
+        &self,
+        fields: &[(&'static str, RedactAction)],
+        records: Vec<T>,
+    ) -> Vec<T> {
+        if fields.is_empty() {
+            return records;
+        }
+        records
+            .into_iter()
+            .map(|record| self.redact(fields, record))
+            .collect()
+    }
+
+    pub fn apply(&self, fcc_db: crate::FccDB) -> crate::FccDB {
+        crate::FccDB {
+            amateur: self.redact_all(&self.amateur, fcc_db.amateur),
+            entity: self.redact_all(&self.entity, fcc_db.entity),
+            application_license_header: self
+                .redact_all(&self.header, fcc_db.application_license_header),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use std::collections::HashMap;
+
+    fn config(fields: &[(&str, RedactAction)], key_file: Option<String>) -> RedactionConfig {
+        RedactionConfig {
+            key_file,
+            fields: fields
+                .iter()
+                .map(|(field, action)| (field.to_string(), action.clone()))
+                .collect::<HashMap<String, RedactAction>>(),
+        }
+    }
+
+    #[test]
+    fn callsign_covers_every_table() {
+        for field in ["callsign", "call_sign"] {
+            let redactor = Redactor::new(&config(&[(field, RedactAction::Drop)], None)).unwrap();
+            let fcc_db = redactor.apply(fixtures::fcc_db());
+            assert!(fcc_db.amateur.iter().all(|am| am.callsign.is_empty()));
+            assert!(fcc_db.entity.iter().all(|en| en.call_sign.is_empty()));
+            assert!(fcc_db
+                .application_license_header
+                .iter()
+                .all(|hd| hd.call_sign.is_empty()));
+        }
+    }
+
+    #[test]
+    fn one_field_configured_twice_is_rejected() {
+        let config = config(
+            &[
+                ("callsign", RedactAction::Drop),
+                ("call_sign", RedactAction::Mask),
+            ],
+            None,
+        );
+        assert!(Redactor::new(&config).is_err());
+    }
+
+    #[test]
+    fn only_tables_with_the_field_change() {
+        let redactor = Redactor::new(&config(&[("email", RedactAction::Mask)], None)).unwrap();
+        assert!(redactor.amateur.is_empty() && redactor.header.is_empty());
+        let original = fixtures::fcc_db();
+        let fcc_db = redactor.apply(fixtures::fcc_db());
+        assert_eq!(fcc_db.entity[0].email, "**********.com");
+        assert_eq!(fcc_db.entity[3].email, "");
+        assert_eq!(
+            serde_json::to_value(&fcc_db.amateur).unwrap(),
+            serde_json::to_value(&original.amateur).unwrap()
+        );
+    }
+
+    #[test]
+    fn hash_is_keyed_and_stable() {
+        let directory = tempfile::tempdir().unwrap();
+        let key_file = directory.path().join("redaction.key");
+        std::fs::write(&key_file, "secret\n").unwrap();
+        let key_file = Some(key_file.to_string_lossy().into_owned());
+        let fields = [("email", RedactAction::Hash)];
+
+        let first = Redactor::new(&config(&fields, key_file.clone())).unwrap();
+        let second = Redactor::new(&config(&fields, key_file.clone())).unwrap();
+        let hashed = first.apply(fixtures::fcc_db()).entity[0].email.clone();
+        assert_eq!(hashed.len(), HASH_LENGTH);
+        assert_ne!(hashed, "u1@example.com");
+        assert_eq!(second.apply(fixtures::fcc_db()).entity[0].email, hashed);
+
+        assert!(Redactor::new(&config(&fields, None)).is_err());
+
+        // Every hashed value must fit its column.
+        for field in ["frn", "callsign", "zip_code", "gender", "mi"] {
+            let fields = [(field, RedactAction::Hash)];
+            assert!(Redactor::new(&config(&fields, key_file.clone())).is_err());
+        }
+    }
+
+    #[test]
+    fn short_values_are_masked_whole() {
+        assert_eq!(mask("M"), "*");
+        assert_eq!(mask("1234"), "****");
+        assert_eq!(mask("55401"), "*5401");
+    }
+
+    #[test]
+    fn identifier_coded_and_unknown_fields_are_rejected() {
+        for field in [
+            "unique_system_identifier",
+            "operator_class",
+            "no_such_field",
+        ] {
+            assert!(Redactor::new(&config(&[(field, RedactAction::Drop)], None)).is_err());
+        }
+    }
+}
@@ -0,0 +1,480 @@
+use mysql::prelude::*;
+use mysql::*;
+
+use crate::config::LicenseOutput;
+use crate::data;
+use crate::sql::SqlRow;
+
+/*
+ * Versioned schema migrations for the ham_* tables.
+ *
+ * Every migration is applied at most once, in order, and its version is recorded in
+ * ham_schema_version.  Migration 1 uses CREATE TABLE IF NOT EXISTS, so databases that were
+ * set up by hand before this existed are adopted as version 1 and upgraded from there.
+ *
+ * Dates are kept as the MM/DD/YYYY strings found in the FCC files.
+ */
+pub const MIGRATIONS: &[(u32, &[&str])] = &[
+    (1, &[CREATE_HAM_AM, CREATE_HAM_EN, CREATE_HAM_HD]),
+    (2, &[ALTER_HAM_EN_LINKED_LICENSE]),
+    (
+        3,
+        &[
+            ALTER_HAM_AM_ROW_HASH,
+            ALTER_HAM_EN_ROW_HASH,
+            ALTER_HAM_HD_ROW_HASH,
+        ],
+    ),
+    (4, &[ALTER_HAM_EN_LOCATION_KEYS]),
+];
+
+const CREATE_HAM_SCHEMA_VERSION: &str = "CREATE TABLE IF NOT EXISTS ham_schema_version (\
+	`version` INT UNSIGNED NOT NULL, \
+	`applied_at` TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, \
+	PRIMARY KEY (`version`)\
+	) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4";
+
+const CREATE_HAM_AM: &str = "CREATE TABLE IF NOT EXISTS ham_AM (\
+	`Record Type` CHAR(2) NOT NULL, \
+	`Unique System Identifier` INT UNSIGNED NOT NULL, \
+	`ULS File Number` VARCHAR(14) NOT NULL, \
+	`EBF Number` VARCHAR(30) NOT NULL, \
+	`Call Sign` VARCHAR(10) NOT NULL, \
+	`Operator Class` CHAR(1) NOT NULL, \
+	`Group Code` CHAR(1) NOT NULL, \
+	`Region Code` INT UNSIGNED NULL, \
+	`Trustee Call Sign` VARCHAR(10) NOT NULL, \
+	`Trustee Indicator` CHAR(1) NOT NULL, \
+	`Physician Certification` CHAR(1) NOT NULL, \
+	`VE Signature` CHAR(1) NOT NULL, \
+	`Systematic Call Sign Change` CHAR(1) NOT NULL, \
+	`Vanity Call Sign Change` CHAR(1) NOT NULL, \
+	`Vanity Relationship` VARCHAR(12) NOT NULL, \
+	`Previous Call Sign` VARCHAR(10) NOT NULL, \
+	`Previous Operator Class` CHAR(1) NOT NULL, \
+	`Trustee Name` VARCHAR(50) NOT NULL, \
+	PRIMARY KEY (`Unique System Identifier`), \
+	KEY `Call Sign` (`Call Sign`)\
+	) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4";
+
+const CREATE_HAM_EN: &str = "CREATE TABLE IF NOT EXISTS ham_EN (\
+	`Record Type` CHAR(2) NOT NULL, \
+	`Unique System Identifier` INT UNSIGNED NOT NULL, \
+	`ULS File Number` VARCHAR(14) NOT NULL, \
+	`EBF Number` VARCHAR(30) NOT NULL, \
+	`Call Sign` VARCHAR(10) NOT NULL, \
+	`Entity Type` CHAR(2) NOT NULL, \
+	`Licensee ID` VARCHAR(9) NOT NULL, \
+	`Entity Name` VARCHAR(200) NOT NULL, \
+	`First Name` VARCHAR(20) NOT NULL, \
+	`MI` CHAR(1) NOT NULL, \
+	`Last Name` VARCHAR(20) NOT NULL, \
+	`Suffix` VARCHAR(3) NOT NULL, \
+	`Phone` VARCHAR(10) NOT NULL, \
+	`Fax` VARCHAR(10) NOT NULL, \
+	`Email` VARCHAR(50) NOT NULL, \
+	`Street Address` VARCHAR(60) NOT NULL, \
+	`City` VARCHAR(20) NOT NULL, \
+	`State` CHAR(2) NOT NULL, \
+	`Zip Code` VARCHAR(9) NOT NULL, \
+	`PO Box` VARCHAR(20) NOT NULL, \
+	`Attention Line` VARCHAR(35) NOT NULL, \
+	`SGIN` VARCHAR(3) NOT NULL, \
+	`FRN` VARCHAR(10) NOT NULL, \
+	`Applicant Type Code` CHAR(1) NOT NULL, \
+	`Status Code` VARCHAR(40) NOT NULL, \
+	`Status Date` VARCHAR(10) NOT NULL, \
+	PRIMARY KEY (`Unique System Identifier`, `Entity Type`), \
+	KEY `Call Sign` (`Call Sign`), \
+	KEY `FRN` (`FRN`)\
+	) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4";
+
+const CREATE_HAM_HD: &str = "CREATE TABLE IF NOT EXISTS ham_HD (\
+	`Record Type` CHAR(2) NOT NULL, \
+	`Unique System Identifier` INT UNSIGNED NOT NULL, \
+	`ULS File Number` VARCHAR(14) NOT NULL, \
+	`EBF Number` VARCHAR(30) NOT NULL, \
+	`Call Sign` VARCHAR(10) NOT NULL, \
+	`License Status` CHAR(1) NOT NULL, \
+	`Radio Service Code` VARCHAR(2) NOT NULL, \
+	`Grant Date` VARCHAR(10) NOT NULL, \
+	`Expired Date` VARCHAR(10) NOT NULL, \
+	`Cancellation Date` VARCHAR(10) NOT NULL, \
+	`Eligibility Rule Num` VARCHAR(10) NOT NULL, \
+	`Reserved` CHAR(1) NOT NULL, \
+	`Alien` CHAR(1) NOT NULL, \
+	`Alien Government` CHAR(1) NOT NULL, \
+	`Alien Corporation` CHAR(1) NOT NULL, \
+	`Alien Officer` CHAR(1) NOT NULL, \
+	`Alien Control` CHAR(1) NOT NULL, \
+	`Revoked` CHAR(1) NOT NULL, \
+	`Convicted` CHAR(1) NOT NULL, \
+	`Adjudged` CHAR(1) NOT NULL, \
+	`Involved Reserved` CHAR(1) NOT NULL, \
+	`Common Carrier` CHAR(1) NOT NULL, \
+	`Non Common Carrier` CHAR(1) NOT NULL, \
+	`Private Comm` CHAR(1) NOT NULL, \
+	`Fixed` CHAR(1) NOT NULL, \
+	`Mobile` CHAR(1) NOT NULL, \
+	`Radiolocation` CHAR(1) NOT NULL, \
+	`Satellite` CHAR(1) NOT NULL, \
+	`Developmental or STA or Demonstration` CHAR(1) NOT NULL, \
+	`Interconnected Service` CHAR(1) NOT NULL, \
+	`Certifier First Name` VARCHAR(20) NOT NULL, \
+	`Certifier MI` CHAR(1) NOT NULL, \
+	`Certifier Last Name` VARCHAR(20) NOT NULL, \
+	`Certifier Suffix` VARCHAR(3) NOT NULL, \
+	`Certifier Title` VARCHAR(40) NOT NULL, \
+	`Gender` CHAR(1) NOT NULL, \
+	`African American` CHAR(1) NOT NULL, \
+	`Native American` CHAR(1) NOT NULL, \
+	`Hawaiian` CHAR(1) NOT NULL, \
+	`Asian` CHAR(1) NOT NULL, \
+	`White` CHAR(1) NOT NULL, \
+	`Ethnicity` CHAR(1) NOT NULL, \
+	`Effective Date` VARCHAR(10) NOT NULL, \
+	`Last Action Date` VARCHAR(10) NOT NULL, \
+	`Auction ID` INT UNSIGNED NULL, \
+	`Broadcast Services - Regulatory Status` CHAR(1) NOT NULL, \
+	`Band Manager` CHAR(1) NOT NULL, \
+	`Broadcast Services - Type of Radio Service` CHAR(1) NOT NULL, \
+	`Alien Ruling` CHAR(1) NOT NULL, \
+	`Licensee Name Change` CHAR(1) NOT NULL, \
+	`Whitespace Ind` CHAR(1) NOT NULL, \
+	`Additional Cert Choice` CHAR(1) NOT NULL, \
+	`Additional Cert Answer` CHAR(1) NOT NULL, \
+	`Discontinuation Ind` CHAR(1) NOT NULL, \
+	`Regulatory Compliance Ind` CHAR(1) NOT NULL, \
+	`Eligibility Cert 900` CHAR(1) NOT NULL, \
+	`Transition Plan Cert 900` CHAR(1) NOT NULL, \
+	`Return Spectrum Cert 900` CHAR(1) NOT NULL, \
+	`Payment Cert 900` CHAR(1) NOT NULL, \
+	PRIMARY KEY (`Unique System Identifier`), \
+	KEY `Call Sign` (`Call Sign`)\
+	) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4";
+
+// EN fields that were parsed but never loaded before version 2.
+const ALTER_HAM_EN_LINKED_LICENSE: &str = "ALTER TABLE ham_EN \
+	ADD COLUMN `Lic Category Code` VARCHAR(10) NOT NULL DEFAULT '', \
+	ADD COLUMN `Linked License ID` VARCHAR(10) NOT NULL DEFAULT '', \
+	ADD COLUMN `Linked Callsign` VARCHAR(10) NOT NULL DEFAULT ''";
+
+// SHA-256 of each row, used by the incremental sync mode.
+const ALTER_HAM_AM_ROW_HASH: &str =
+    "ALTER TABLE ham_AM ADD COLUMN `Row Hash` CHAR(64) NOT NULL DEFAULT ''";
+const ALTER_HAM_EN_ROW_HASH: &str =
+    "ALTER TABLE ham_EN ADD COLUMN `Row Hash` CHAR(64) NOT NULL DEFAULT ''";
+const ALTER_HAM_HD_ROW_HASH: &str =
+    "ALTER TABLE ham_HD ADD COLUMN `Row Hash` CHAR(64) NOT NULL DEFAULT ''";
+
+// The ham_license view filters on these through ham_EN.
+const ALTER_HAM_EN_LOCATION_KEYS: &str = "ALTER TABLE ham_EN \
+	ADD KEY `State` (`State`), \
+	ADD KEY `Zip Code` (`Zip Code`)";
+
+/*
+ * The joined license record, one row per license with the License fields flattened.  It is not a
+ * migration because it is optional, and may be a table loaded like the others or a view over
+ * them, depending on mysql_config.license_output.
+ */
+const CREATE_HAM_LICENSE: &str = "CREATE TABLE IF NOT EXISTS ham_license (\
+	`Unique System Identifier` INT UNSIGNED NOT NULL, \
+	`ULS File Number` VARCHAR(14) NOT NULL, \
+	`EBF Number` VARCHAR(30) NOT NULL, \
+	`Call Sign` VARCHAR(10) NOT NULL, \
+	`Operator Class` CHAR(1) NOT NULL, \
+	`Group Code` CHAR(1) NOT NULL, \
+	`Region Code` INT UNSIGNED NULL, \
+	`Trustee Call Sign` VARCHAR(10) NOT NULL, \
+	`Trustee Indicator` CHAR(1) NOT NULL, \
+	`Physician Certification` CHAR(1) NOT NULL, \
+	`VE Signature` CHAR(1) NOT NULL, \
+	`Systematic Call Sign Change` CHAR(1) NOT NULL, \
+	`Vanity Call Sign Change` CHAR(1) NOT NULL, \
+	`Vanity Relationship` VARCHAR(12) NOT NULL, \
+	`Previous Call Sign` VARCHAR(10) NOT NULL, \
+	`Previous Operator Class` CHAR(1) NOT NULL, \
+	`Trustee Name` VARCHAR(50) NOT NULL, \
+	`Entity Type` CHAR(2) NOT NULL, \
+	`Licensee ID` VARCHAR(9) NOT NULL, \
+	`Entity Name` VARCHAR(200) NOT NULL, \
+	`First Name` VARCHAR(20) NOT NULL, \
+	`MI` CHAR(1) NOT NULL, \
+	`Last Name` VARCHAR(20) NOT NULL, \
+	`Suffix` VARCHAR(3) NOT NULL, \
+	`Phone` VARCHAR(10) NOT NULL, \
+	`Fax` VARCHAR(10) NOT NULL, \
+	`Email` VARCHAR(50) NOT NULL, \
+	`Street Address` VARCHAR(60) NOT NULL, \
+	`City` VARCHAR(20) NOT NULL, \
+	`State` CHAR(2) NOT NULL, \
+	`Zip Code` VARCHAR(9) NOT NULL, \
+	`PO Box` VARCHAR(20) NOT NULL, \
+	`Attention Line` VARCHAR(35) NOT NULL, \
+	`SGIN` VARCHAR(3) NOT NULL, \
+	`FRN` VARCHAR(10) NOT NULL, \
+	`Applicant Type Code` CHAR(1) NOT NULL, \
+	`Status Code` VARCHAR(40) NOT NULL, \
+	`Status Date` VARCHAR(10) NOT NULL, \
+	`Lic Category Code` VARCHAR(10) NOT NULL, \
+	`Linked License ID` VARCHAR(10) NOT NULL, \
+	`Linked Callsign` VARCHAR(10) NOT NULL, \
+	`License Status` CHAR(1) NOT NULL, \
+	`Radio Service Code` VARCHAR(2) NOT NULL, \
+	`Grant Date` VARCHAR(10) NOT NULL, \
+	`Expired Date` VARCHAR(10) NOT NULL, \
+	`Cancellation Date` VARCHAR(10) NOT NULL, \
+	`Eligibility Rule Num` VARCHAR(10) NOT NULL, \
+	`Reserved` CHAR(1) NOT NULL, \
+	`Alien` CHAR(1) NOT NULL, \
+	`Alien Government` CHAR(1) NOT NULL, \
+	`Alien Corporation` CHAR(1) NOT NULL, \
+	`Alien Officer` CHAR(1) NOT NULL, \
+	`Alien Control` CHAR(1) NOT NULL, \
+	`Revoked` CHAR(1) NOT NULL, \
+	`Convicted` CHAR(1) NOT NULL, \
+	`Adjudged` CHAR(1) NOT NULL, \
+	`Involved Reserved` CHAR(1) NOT NULL, \
+	`Common Carrier` CHAR(1) NOT NULL, \
+	`Non Common Carrier` CHAR(1) NOT NULL, \
+	`Private Comm` CHAR(1) NOT NULL, \
+	`Fixed` CHAR(1) NOT NULL, \
+	`Mobile` CHAR(1) NOT NULL, \
+	`Radiolocation` CHAR(1) NOT NULL, \
+	`Satellite` CHAR(1) NOT NULL, \
+	`Developmental or STA or Demonstration` CHAR(1) NOT NULL, \
+	`Interconnected Service` CHAR(1) NOT NULL, \
+	`Certifier First Name` VARCHAR(20) NOT NULL, \
+	`Certifier MI` CHAR(1) NOT NULL, \
+	`Certifier Last Name` VARCHAR(20) NOT NULL, \
+	`Certifier Suffix` VARCHAR(3) NOT NULL, \
+	`Certifier Title` VARCHAR(40) NOT NULL, \
+	`Gender` CHAR(1) NOT NULL, \
+	`African American` CHAR(1) NOT NULL, \
+	`Native American` CHAR(1) NOT NULL, \
+	`Hawaiian` CHAR(1) NOT NULL, \
+	`Asian` CHAR(1) NOT NULL, \
+	`White` CHAR(1) NOT NULL, \
+	`Ethnicity` CHAR(1) NOT NULL, \
+	`Effective Date` VARCHAR(10) NOT NULL, \
+	`Last Action Date` VARCHAR(10) NOT NULL, \
+	`Auction ID` INT UNSIGNED NULL, \
+	`Broadcast Services - Regulatory Status` CHAR(1) NOT NULL, \
+	`Band Manager` CHAR(1) NOT NULL, \
+	`Broadcast Services - Type of Radio Service` CHAR(1) NOT NULL, \
+	`Alien Ruling` CHAR(1) NOT NULL, \
+	`Licensee Name Change` CHAR(1) NOT NULL, \
+	`Whitespace Ind` CHAR(1) NOT NULL, \
+	`Additional Cert Choice` CHAR(1) NOT NULL, \
+	`Additional Cert Answer` CHAR(1) NOT NULL, \
+	`Discontinuation Ind` CHAR(1) NOT NULL, \
+	`Regulatory Compliance Ind` CHAR(1) NOT NULL, \
+	`Eligibility Cert 900` CHAR(1) NOT NULL, \
+	`Transition Plan Cert 900` CHAR(1) NOT NULL, \
+	`Return Spectrum Cert 900` CHAR(1) NOT NULL, \
+	`Payment Cert 900` CHAR(1) NOT NULL, \
+	`Row Hash` CHAR(64) NOT NULL DEFAULT '', \
+	PRIMARY KEY (`Unique System Identifier`), \
+	KEY `Call Sign` (`Call Sign`), \
+	KEY `FRN` (`FRN`), \
+	KEY `State` (`State`), \
+	KEY `Zip Code` (`Zip Code`)\
+	) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4";
+
+// The declared width of a CHAR or VARCHAR column in the ham_* tables, the narrowest if they differ.
+pub fn column_width(column: &str) -> Option<usize> {
+    let definition = format!("`{}` ", column);
+    MIGRATIONS
+        .iter()
+        .flat_map(|(_version, migration)| migration.iter())
+        .filter_map(|statement| {
+            let declared = &statement[statement.find(&definition)? + definition.len()..];
+            let width = declared.strip_prefix("VAR").unwrap_or(declared);
+            let width = width.strip_prefix("CHAR(")?;
+            width[..width.find(')')?].parse().ok()
+        })
+        .min()
+}
+
+/*
+ * ham_license with just these columns, keeping the row hash and the keys on columns it keeps.
+ * The identifier and its primary key are always kept.
+ */
+fn license_table_statement(columns: &[&str]) -> String {
+    let kept = |column: &str| column == "Unique System Identifier" || columns.contains(&column);
+    let start = CREATE_HAM_LICENSE.find('(').unwrap() + 1;
+    let end = CREATE_HAM_LICENSE.rfind(") ENGINE").unwrap();
+    let definitions: Vec<&str> = CREATE_HAM_LICENSE[start..end]
+        .split(", ")
+        .filter(|definition| {
+            let name = definition.split('`').nth(1).unwrap_or_default();
+            let keyed = definition.split('(').nth(1).unwrap_or_default();
+            if definition.starts_with('`') {
+                name == crate::sql::ROW_HASH_COLUMN || kept(name)
+            } else {
+                keyed
+                    .strip_prefix('`')
+                    .and_then(|keyed| keyed.strip_suffix("`)"))
+                    .is_some_and(kept)
+            }
+        })
+        .collect();
+    format!(
+        "{}{}{}",
+        &CREATE_HAM_LICENSE[..start],
+        definitions.join(", "),
+        &CREATE_HAM_LICENSE[end..]
+    )
+}
+
+// The alias of the table a ham_license view takes the column from: am, en or hd.
+pub fn license_column_alias(column: &str) -> &'static str {
+    if data::Amateur::COLUMNS.iter().any(|(_f, c)| *c == column) {
+        "am"
+    } else if data::Entity::COLUMNS.iter().any(|(_f, c)| *c == column) {
+        "en"
+    } else {
+        "hd"
+    }
+}
+
+/*
+ * AM columns come from ham_AM, then the licensee from ham_EN, then the rest from ham_HD.  The
+ * view is always an inner join, whatever join is set to: only the ham_license table follows it.
+ */
+fn license_view_statement(columns: &[&str]) -> String {
+    let columns: Vec<String> = columns
+        .iter()
+        .map(|column| format!("{}.`{}`", license_column_alias(column), column))
+        .collect();
+    format!(
+        "CREATE OR REPLACE VIEW ham_license AS SELECT {} FROM ham_AM am \
+         JOIN ham_EN en ON en.`Unique System Identifier` = am.`Unique System Identifier` \
+         AND en.`Entity Type` = 'L' \
+         JOIN ham_HD hd ON hd.`Unique System Identifier` = am.`Unique System Identifier`",
+        columns.join(", ")
+    )
+}
+
+fn license_columns() -> Vec<&'static str> {
+    crate::License::COLUMNS
+        .iter()
+        .map(|(_field, column)| *column)
+        .collect()
+}
+
+// Replaces a ham_license of the other kind, so switching license_output needs no manual cleanup.
+pub fn create_license(sql_url: &str, output: &LicenseOutput) -> mysql::Result<()> {
+    let pool = Pool::new(sql_url)?;
+    let mut conn = pool.get_conn()?;
+    let existing: Option<String> = conn.query_first(
+        "SELECT TABLE_TYPE FROM information_schema.TABLES \
+         WHERE TABLE_SCHEMA = DATABASE() AND TABLE_NAME = 'ham_license'",
+    )?;
+    match (output, existing.as_deref()) {
+        (LicenseOutput::Table, Some("VIEW")) => conn.query_drop("DROP VIEW ham_license")?,
+        (LicenseOutput::View, Some("BASE TABLE")) => conn.query_drop("DROP TABLE ham_license")?,
+        _ => (),
+    }
+    match output {
+        LicenseOutput::None => Ok(()),
+        LicenseOutput::Table => conn.query_drop(CREATE_HAM_LICENSE),
+        LicenseOutput::View => conn.query_drop(license_view_statement(&license_columns())),
+    }
+}
+
+/*
+ * Every statement needed to build the current schema in an empty database, for offline dumps.
+ * ham_license has just the given columns.
+ */
+pub fn schema_statements(output: &LicenseOutput, license_columns: &[&str]) -> Vec<String> {
+    let mut statements = vec![CREATE_HAM_SCHEMA_VERSION.to_string()];
+    for (version, migration) in MIGRATIONS {
+        statements.extend(migration.iter().map(|statement| statement.to_string()));
+        statements.push(format!(
+            "INSERT INTO ham_schema_version (`version`) VALUES ({})",
+            version
+        ));
+    }
+    match output {
+        LicenseOutput::None => (),
+        LicenseOutput::Table => statements.push(license_table_statement(license_columns)),
+        LicenseOutput::View => statements.push(license_view_statement(license_columns)),
+    }
+    statements
+}
+
+fn schema_version(conn: &mut PooledConn) -> mysql::Result<u32> {
+    let version: Option<Option<u32>> =
+        conn.query_first("SELECT MAX(`version`) FROM ham_schema_version")?;
+    Ok(version.flatten().unwrap_or(0))
+}
+
+pub fn migrate(sql_url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let pool = Pool::new(sql_url)?;
+    let mut conn = pool.get_conn()?;
+
+    // Keep two loaders from migrating the same database at once.  GET_LOCK returns 0 on a
+    // timeout and NULL on an error, and only 1 means the lock is held.
+    let locked: Option<Option<u8>> =
+        conn.query_first("SELECT GET_LOCK('ham_schema_migration', 60)")?;
+    if locked.flatten() != Some(1) {
+        return Err("could not get the ham_schema_migration lock within 60 seconds".into());
+    }
+    let result = apply_migrations(&mut conn);
+    conn.query_drop("SELECT RELEASE_LOCK('ham_schema_migration')")?;
+    Ok(result?)
+}
+
+fn apply_migrations(conn: &mut PooledConn) -> mysql::Result<()> {
+    conn.query_drop(CREATE_HAM_SCHEMA_VERSION)?;
+    let current = schema_version(conn)?;
+    println!("ham_* schema is at version {}", current);
+
+    for (version, statements) in MIGRATIONS {
+        if *version <= current {
+            continue;
+        }
+        println!("Applying schema migration {}", version);
+        // MySQL commits DDL implicitly, so a migration cannot be rolled back as a whole.
+        for statement in statements.iter() {
+            conn.query_drop(statement)?;
+        }
+        conn.exec_drop(
+            "INSERT INTO ham_schema_version (`version`) VALUES (?)",
+            (version,),
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn license_table_keeps_its_primary_key() {
+        let statement = license_table_statement(&["Call Sign", "State"]);
+        assert_eq!(
+            statement,
+            "CREATE TABLE IF NOT EXISTS ham_license (\
+             `Unique System Identifier` INT UNSIGNED NOT NULL, \
+             `Call Sign` VARCHAR(10) NOT NULL, \
+             `State` CHAR(2) NOT NULL, \
+             `Row Hash` CHAR(64) NOT NULL DEFAULT '', \
+             PRIMARY KEY (`Unique System Identifier`), \
+             KEY `Call Sign` (`Call Sign`), \
+             KEY `State` (`State`)\
+             ) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4"
+        );
+    }
+
+    #[test]
+    fn column_widths_come_from_the_migrations() {
+        assert_eq!(column_width("Call Sign"), Some(10));
+        assert_eq!(column_width("Gender"), Some(1));
+        assert_eq!(column_width("Linked Callsign"), Some(10));
+        assert_eq!(column_width("Row Hash"), Some(64));
+        assert_eq!(column_width("Unique System Identifier"), None);
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::FccDB;
+
+/*
+ * Versioned snapshot of the parsed FCC database.
+ *
+ * The file starts with an 8 byte magic and a big-endian u32 format version, followed by a CBOR
+ * header and the CBOR-encoded FccDB.  The header can be read on its own to see what a snapshot
+ * holds without decoding the records.
+ */
+const MAGIC: &[u8; 8] = b"FCCSNAP\0";
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SnapshotHeader {
+    // When the FCC built the extract the snapshot was parsed from, if known.
+    pub source_timestamp: Option<String>,
+    // When the snapshot was written, in RFC 3339.
+    pub created: String,
+    pub amateur_count: usize,
+    pub entity_count: usize,
+    pub application_license_header_count: usize,
+}
+
+pub fn write_snapshot(
+    filename: &str,
+    fcc_db: &FccDB,
+    source_timestamp: Option<String>,
+) -> Result<SnapshotHeader, Box<dyn std::error::Error>> {
+    let header = SnapshotHeader {
+        source_timestamp,
+        created: chrono::Utc::now().to_rfc3339(),
+        amateur_count: fcc_db.amateur.len(),
+        entity_count: fcc_db.entity.len(),
+        application_license_header_count: fcc_db.application_license_header.len(),
+    };
+
+    let tmp_filename = format!("{}.tmp", filename);
+    let mut out = BufWriter::new(File::create(&tmp_filename)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&VERSION.to_be_bytes())?;
+    ciborium::ser::into_writer(&header, &mut out)?;
+    ciborium::ser::into_writer(fcc_db, &mut out)?;
+    out.into_inner()?.sync_all()?;
+    std::fs::rename(&tmp_filename, filename)?;
+    Ok(header)
+}
+
+fn read_header_from(
+    filename: &str,
+    input: &mut impl Read,
+) -> Result<SnapshotHeader, Box<dyn std::error::Error>> {
+    let mut magic = [0u8; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(format!("{} is not an FCC snapshot", filename).into());
+    }
+    let mut version = [0u8; 4];
+    input.read_exact(&mut version)?;
+    let version = u32::from_be_bytes(version);
+    if version != VERSION {
+        return Err(format!(
+            "{} is snapshot version {}, expected {}",
+            filename, version, VERSION
+        )
+        .into());
+    }
+    Ok(ciborium::de::from_reader(input)?)
+}
+
+pub fn read_header(filename: &str) -> Result<SnapshotHeader, Box<dyn std::error::Error>> {
+    let mut input = BufReader::new(File::open(filename)?);
+    read_header_from(filename, &mut input)
+}
+
+pub fn read_snapshot(
+    filename: &str,
+) -> Result<(SnapshotHeader, FccDB), Box<dyn std::error::Error>> {
+    let mut input = BufReader::new(File::open(filename)?);
+    let header = read_header_from(filename, &mut input)?;
+    let fcc_db: FccDB = ciborium::de::from_reader(&mut input)?;
+    let counts = (
+        fcc_db.amateur.len(),
+        fcc_db.entity.len(),
+        fcc_db.application_license_header.len(),
+    );
+    if counts
+        != (
+            header.amateur_count,
+            header.entity_count,
+            header.application_license_header_count,
+        )
+    {
+        return Err(format!("{} record counts do not match its header", filename).into());
+    }
+    Ok((header, fcc_db))
+}
+
+impl FccDB {
+    pub fn load_snapshot(filename: &str) -> Result<FccDB, Box<dyn std::error::Error>> {
+        let (_header, fcc_db) = read_snapshot(filename)?;
+        Ok(fcc_db)
+    }
+
+    // A snapshot file, or a directory holding the extracted .dat files.
+    pub fn load(path: &str) -> Result<FccDB, Box<dyn std::error::Error>> {
+        let directory = Path::new(path);
+        if !directory.is_dir() {
+            return FccDB::load_snapshot(path);
+        }
+        let file = |name: &str| -> Result<String, Box<dyn std::error::Error>> {
+            let file = directory.join(name);
+            if !file.is_file() {
+                return Err(format!("{} not found", file.display()).into());
+            }
+            Ok(file.to_string_lossy().into_owned())
+        };
+        Ok(FccDB {
+            amateur: crate::parse_am_file(&file("AM.dat")?).0,
+            entity: crate::parse_en_file(&file("EN.dat")?).0,
+            application_license_header: crate::parse_hd_file(&file("HD.dat")?).0,
+        })
+    }
+}
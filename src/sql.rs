@@ -2,9 +2,17 @@ use indicatif::{MultiProgress, ProgressBar};
 use mysql::prelude::*;
 use mysql::*;
 use rayon::ThreadPoolBuilder;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::convert::From;
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
+use crate::config::{LoadMode, MySQLConfig};
 use crate::data;
 
 // Begin Enums
@@ -138,608 +146,910 @@ impl From<data::DevelopmentalStaDemonstration> for mysql::Value {
 }
 // End Enums
 
-// BEGIN ham_AM //
-fn split_am_rows(input_records: Vec<data::Amateur>) -> Vec<Vec<data::Amateur>> {
-    let mut retval: Vec<Vec<data::Amateur>> = Vec::new();
-    let mut i = 0;
-    let mut n = 0;
-    let split_modulus = input_records.len() / 10;
-    for am_row in input_records {
-        if (n % split_modulus) == 0 {
-            let mut this_vec: Vec<data::Amateur> = Vec::new();
-            this_vec.push(am_row);
-            retval.push(this_vec);
-            i = retval.len() - 1;
-        } else {
-            retval[i].push(am_row);
-        }
-        n = n + 1;
+// BEGIN staging tables //
+/*
+ * Each load goes into <table>_staging, which is swapped in with a single RENAME TABLE once
+ * every chunk has committed.  Readers keep seeing the previous contents until then, and a
+ * failed load leaves the live table untouched.
+ */
+fn create_staging_table(conn: &mut PooledConn, table: &str) -> mysql::Result<()> {
+    conn.query_drop(format!("DROP TABLE IF EXISTS `{}_staging`", table))?;
+    conn.query_drop(format!("CREATE TABLE `{0}_staging` LIKE `{0}`", table))
+}
+
+fn finish_staging_table(
+    conn: &mut PooledConn,
+    table: &str,
+    failed_chunks: usize,
+    tot_chunks: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if failed_chunks > 0 {
+        println!("Discarding {}_staging", table);
+        conn.query_drop(format!("DROP TABLE IF EXISTS `{}_staging`", table))?;
+        return Err(format!(
+            "{}/{} chunks failed, {} was left unchanged",
+            failed_chunks, tot_chunks, table
+        )
+        .into());
     }
-    return retval;
+
+    println!("Swapping {}_staging into place", table);
+    conn.query_drop(format!("DROP TABLE IF EXISTS `{}_old`", table))?;
+    conn.query_drop(format!(
+        "RENAME TABLE `{0}` TO `{0}_old`, `{0}_staging` TO `{0}`",
+        table
+    ))?;
+    conn.query_drop(format!("DROP TABLE `{}_old`", table))?;
+    Ok(())
 }
+// END staging tables //
 
-fn do_am_drop(mut conn: mysql::PooledConn) -> mysql::Result<()> {
-    let mut tx = conn.start_transaction(TxOpts::default()).unwrap();
-    let result = tx.exec_drop("DELETE FROM ham_AM", ());
-    tx.commit().unwrap();
-    return result;
+// BEGIN row mappings //
+/*
+ * A record type that can be loaded into one of the ham_* tables.
+ *
+ * COLUMNS pairs every field of the record with its SQL column, and values() returns the field
+ * values in the same order.  Both are generated by sql_row! below.  KEY and INDEXES name the
+ * primary key and indexed columns, for backends that build their own DDL.
+ */
+pub trait SqlRow: Send + Sync {
+    const TABLE: &'static str;
+    const COLUMNS: &'static [(&'static str, &'static str)];
+    const KEY: &'static [&'static str];
+    const INDEXES: &'static [&'static str];
+
+    fn values(&self) -> Vec<Value>;
 }
 
-fn insert_am_rows_batch(
-    mut conn: mysql::PooledConn,
-    am_records: Vec<data::Amateur>,
-    chunk_id: u32,
-    tot_chunks: usize,
-    pb: &ProgressBar,
-) {
-    let mut tx = conn.start_transaction(TxOpts::default()).unwrap();
-    let result = tx.exec_batch(
-        "INSERT INTO ham_AM (\
-				`Record Type`, \
-				`Unique System Identifier`, \
-				`ULS File Number`, \
-				`EBF Number`, \
-				`Call Sign`, \
-				`Operator Class`, \
-				`Group Code`, \
-				`Region Code`, \
-				`Trustee Call Sign`, \
-				`Trustee Indicator`, \
-				`Physician Certification`, \
-				`VE Signature`, \
-				`Systematic Call Sign Change`, \
-				`Vanity Call Sign Change`, \
-				`Vanity Relationship`, \
-				`Previous Call Sign`, \
-				`Previous Operator Class`, \
-				`Trustee Name`) \
-				VALUES (\
-				:record_type, \
-				:unique_system_identifier, \
-				:uls_file_num, \
-				:ebf_number, \
-				:callsign, \
-				:operator_class, \
-				:group_code, \
-				:region_code, \
-				:trustee_callsign, \
-				:trustee_indicator, \
-				:physician_certification, \
-				:ve_signature, \
-				:systematic_callsign_change, \
-				:vanity_callsign_change, \
-				:vanity_relationship, \
-				:previous_callsign, \
-				:previous_operator_class, \
-				:trustee_name \
-				)",
-        am_records.iter().map(|p| {
-            pb.inc(1);
-            params! {
-            "record_type" => p.record_type.clone(),
-            "unique_system_identifier" => p.unique_system_identifier,
-            "uls_file_num" => p.uls_file_num.clone(),
-            "ebf_number" => p.ebf_number.clone(),
-            "callsign" => p.callsign.clone(),
-            "operator_class" => p.operator_class.clone(),
-            "group_code" => p.group_code.clone(),
-            "region_code" => p.region_code.clone(),
-            "trustee_callsign" => p.trustee_callsign.clone(),
-            "trustee_indicator" => p.trustee_indicator.clone(),
-            "physician_certification" => p.physician_certification.clone(),
-            "ve_signature" => p.ve_signature.clone(),
-            "systematic_callsign_change" => p.systematic_callsign_change.clone(),
-            "vanity_callsign_change" => p.vanity_callsign_change.clone(),
-            "vanity_relationship" => p.vanity_relationship.clone(),
-            "previous_callsign" => p.previous_callsign.clone(),
-            "previous_operator_class" => p.previous_operator_class.clone(),
-            "trustee_name" => p.trustee_name.clone(),
+pub enum ColumnType {
+    Integer,
+    NullableInteger,
+    Text,
+}
+
+// Everything but the identifier and the U64Null fields is stored as text.
+pub fn column_type(column: &str) -> ColumnType {
+    match column {
+        "Unique System Identifier" => ColumnType::Integer,
+        "Region Code" | "Auction ID" => ColumnType::NullableInteger,
+        _ => ColumnType::Text,
+    }
+}
+
+/*
+ * values() destructures the record without `..`, so adding a field to one of the data structs
+ * fails to compile until that field is mapped to a column here.
+ */
+macro_rules! sql_row {
+    (
+        $record:ty,
+        $table:literal,
+        key: [$($key:literal),*],
+        indexes: [$($index:literal),*],
+        { $($field:ident => $column:literal),* $(,)? }
+    ) => {
+        impl SqlRow for $record {
+            const TABLE: &'static str = $table;
+            const COLUMNS: &'static [(&'static str, &'static str)] =
+                &[$((stringify!($field), $column)),*];
+            const KEY: &'static [&'static str] = &[$($key),*];
+            const INDEXES: &'static [&'static str] = &[$($index),*];
+
+            fn values(&self) -> Vec<Value> {
+                let Self { $($field),* } = self;
+                vec![$(Value::from($field.clone())),*]
             }
-        }),
-    );
-    match result {
-        Ok(_result_value) => {
-            tx.commit().unwrap();
-        }
-        Err(result_value) => {
-            println!("Error: {:#?}", result_value);
-            tx.rollback().unwrap();
         }
     };
-    pb.finish();
-    println!("Chunk {}/{} complete", chunk_id, tot_chunks);
 }
 
-pub fn insert_am_rows(sql_url: &str, am_records: Vec<data::Amateur>) {
-    let pool = Pool::new(sql_url).unwrap();
+sql_row!(
+    data::Amateur,
+    "ham_AM",
+    key: ["Unique System Identifier"],
+    indexes: ["Call Sign"],
+    {
+        record_type => "Record Type",
+        unique_system_identifier => "Unique System Identifier",
+        uls_file_num => "ULS File Number",
+        ebf_number => "EBF Number",
+        callsign => "Call Sign",
+        operator_class => "Operator Class",
+        group_code => "Group Code",
+        region_code => "Region Code",
+        trustee_callsign => "Trustee Call Sign",
+        trustee_indicator => "Trustee Indicator",
+        physician_certification => "Physician Certification",
+        ve_signature => "VE Signature",
+        systematic_callsign_change => "Systematic Call Sign Change",
+        vanity_callsign_change => "Vanity Call Sign Change",
+        vanity_relationship => "Vanity Relationship",
+        previous_callsign => "Previous Call Sign",
+        previous_operator_class => "Previous Operator Class",
+        trustee_name => "Trustee Name",
+    }
+);
 
-    println!("Splitting rows");
-    let am_records_split = split_am_rows(am_records);
-    println!("Rows split into {} chunks", am_records_split.len());
+sql_row!(
+    data::Entity,
+    "ham_EN",
+    key: ["Unique System Identifier", "Entity Type"],
+    indexes: ["Call Sign", "FRN", "State", "Zip Code"],
+    {
+        record_type => "Record Type",
+        unique_system_identifier => "Unique System Identifier",
+        uls_file_num => "ULS File Number",
+        ebf_number => "EBF Number",
+        call_sign => "Call Sign",
+        entity_type => "Entity Type",
+        licensee_id => "Licensee ID",
+        entity_name => "Entity Name",
+        first_name => "First Name",
+        mi => "MI",
+        last_name => "Last Name",
+        suffix => "Suffix",
+        phone => "Phone",
+        fax => "Fax",
+        email => "Email",
+        street_address => "Street Address",
+        city => "City",
+        state => "State",
+        zip_code => "Zip Code",
+        po_box => "PO Box",
+        attention_line => "Attention Line",
+        sgin => "SGIN",
+        frn => "FRN",
+        applicant_type_code => "Applicant Type Code",
+        status_code => "Status Code",
+        status_date => "Status Date",
+        lic_category_code => "Lic Category Code",
+        linked_license_id => "Linked License ID",
+        linked_callsign => "Linked Callsign",
+    }
+);
 
-    println!("Dropping ham_AM");
-    do_am_drop(pool.get_conn().unwrap()).unwrap();
+sql_row!(
+    data::ApplicationLicenseHeader,
+    "ham_HD",
+    key: ["Unique System Identifier"],
+    indexes: ["Call Sign"],
+    {
+        record_type => "Record Type",
+        unique_system_identifier => "Unique System Identifier",
+        uls_file_num => "ULS File Number",
+        ebf_number => "EBF Number",
+        call_sign => "Call Sign",
+        license_status => "License Status",
+        radio_service_code => "Radio Service Code",
+        grant_date => "Grant Date",
+        expired_date => "Expired Date",
+        cancellation_date => "Cancellation Date",
+        eligibility_rule_num => "Eligibility Rule Num",
+        applicant_type_code_reserved => "Reserved",
+        alien => "Alien",
+        alien_government => "Alien Government",
+        alien_corporation => "Alien Corporation",
+        alien_officer => "Alien Officer",
+        alien_control => "Alien Control",
+        revoked => "Revoked",
+        convicted => "Convicted",
+        adjudged => "Adjudged",
+        involved_reserved => "Involved Reserved",
+        common_carrier => "Common Carrier",
+        non_common_carrier => "Non Common Carrier",
+        private_comm => "Private Comm",
+        fixed => "Fixed",
+        mobile => "Mobile",
+        radiolocation => "Radiolocation",
+        satellite => "Satellite",
+        developmental_or_sta => "Developmental or STA or Demonstration",
+        interconnected_service => "Interconnected Service",
+        certifier_first_name => "Certifier First Name",
+        certifier_mi => "Certifier MI",
+        certifier_last_name => "Certifier Last Name",
+        certifier_suffix => "Certifier Suffix",
+        certifier_title => "Certifier Title",
+        gender => "Gender",
+        african_american => "African American",
+        native_american => "Native American",
+        hawaiian => "Hawaiian",
+        asian => "Asian",
+        white => "White",
+        ethnicity => "Ethnicity",
+        effective_date => "Effective Date",
+        last_action_date => "Last Action Date",
+        auction_id => "Auction ID",
+        reg_stat_broad_serv => "Broadcast Services - Regulatory Status",
+        band_manager => "Band Manager",
+        type_serv_broad_serv => "Broadcast Services - Type of Radio Service",
+        alien_ruling => "Alien Ruling",
+        licensee_name_change => "Licensee Name Change",
+        whitespace_ind => "Whitespace Ind",
+        additional_cert_choice => "Additional Cert Choice",
+        additional_cert_answer => "Additional Cert Answer",
+        discontinuation_ind => "Discontinuation Ind",
+        regulatory_compliance_ind => "Regulatory Compliance Ind",
+        eligibility_cert_900 => "Eligibility Cert 900",
+        transition_plan_cert_900 => "Transition Plan Cert 900",
+        return_spectrum_cert_900 => "Return Spectrum Cert 900",
+        payment_cert_900 => "Payment Cert 900",
+    }
+);
 
-    println!("Inserting rows");
-    let multiprogress_bar = Arc::new(MultiProgress::new());
-    let tpool = ThreadPoolBuilder::new().num_threads(10).build().unwrap();
-    let mut this_chunk = 0;
-    let tot_chunks = am_records_split.len();
-    let mp_clone = multiprogress_bar.clone();
-    println!("Entering In Place Scope");
-    tpool.in_place_scope(move |s| {
-        for am_chunk in am_records_split {
-            let conn = pool.get_conn().unwrap();
-            this_chunk = this_chunk + 1;
-            let m_clone = multiprogress_bar.clone();
-            s.spawn(move |_| {
-                let pb2 = m_clone.add(ProgressBar::new(am_chunk.len().try_into().unwrap()));
-                insert_am_rows_batch(conn, am_chunk, this_chunk, tot_chunks, &pb2);
-            });
+/*
+ * The joined license record: AM without its record type, then the licensee from EN and the
+ * HD fields, each without the five leading columns they share with AM.  A missing part of a
+ * left-joined license is loaded as blanks.
+ */
+const SHARED_COLUMNS: usize = 5;
+
+const LICENSE_COLUMN_COUNT: usize = <data::Amateur as SqlRow>::COLUMNS.len() - 1
+    + <data::Entity as SqlRow>::COLUMNS.len()
+    - SHARED_COLUMNS
+    + <data::ApplicationLicenseHeader as SqlRow>::COLUMNS.len()
+    - SHARED_COLUMNS;
+
+// Copies columns[skip..] into license_columns from position n, returning the next position.
+const fn append_columns(
+    license_columns: &mut [(&'static str, &'static str); LICENSE_COLUMN_COUNT],
+    mut n: usize,
+    columns: &'static [(&'static str, &'static str)],
+    skip: usize,
+) -> usize {
+    let mut i = skip;
+    while i < columns.len() {
+        license_columns[n] = columns[i];
+        n += 1;
+        i += 1;
+    }
+    n
+}
+
+const LICENSE_COLUMNS: [(&str, &str); LICENSE_COLUMN_COUNT] = {
+    let mut columns = [("", ""); LICENSE_COLUMN_COUNT];
+    let n = append_columns(&mut columns, 0, <data::Amateur as SqlRow>::COLUMNS, 1);
+    let n = append_columns(
+        &mut columns,
+        n,
+        <data::Entity as SqlRow>::COLUMNS,
+        SHARED_COLUMNS,
+    );
+    let n = append_columns(
+        &mut columns,
+        n,
+        <data::ApplicationLicenseHeader as SqlRow>::COLUMNS,
+        SHARED_COLUMNS,
+    );
+    assert!(n == LICENSE_COLUMN_COUNT);
+    columns
+};
+
+fn values_or_blank<T: SqlRow + Default>(record: &Option<T>) -> Vec<Value> {
+    match record {
+        Some(record) => record.values(),
+        None => T::default().values(),
+    }
+}
+
+impl SqlRow for crate::License {
+    const TABLE: &'static str = "ham_license";
+    const COLUMNS: &'static [(&'static str, &'static str)] = &LICENSE_COLUMNS;
+    const KEY: &'static [&'static str] = &["Unique System Identifier"];
+    const INDEXES: &'static [&'static str] = &["Call Sign", "FRN", "State", "Zip Code"];
+
+    fn values(&self) -> Vec<Value> {
+        let amateur = values_or_blank(&self.amateur);
+        let licensee = values_or_blank(&self.licensee);
+        let header = values_or_blank(&self.header);
+        let shared = match self.amateur {
+            Some(_) => &amateur[1..SHARED_COLUMNS],
+            None => &header[1..SHARED_COLUMNS],
+        };
+        let mut values = shared.to_vec();
+        values.extend_from_slice(&amateur[SHARED_COLUMNS..]);
+        values.extend_from_slice(&licensee[SHARED_COLUMNS..]);
+        values.extend_from_slice(&header[SHARED_COLUMNS..]);
+        values
+    }
+}
+
+/*
+ * Tab-separated text with backslash escapes and \N for NULL, the format read by PostgreSQL's
+ * COPY ... FROM STDIN and by MySQL's LOAD DATA with its default FIELDS and LINES options.
+ */
+pub fn write_text_row(out: &mut Vec<u8>, values: &[Value]) {
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            out.push(b'\t');
         }
-    });
-    let _ = mp_clone.clear();
-    println!("In Place Scope exited");
-    //let _ = tx.commit();
-    //let commit_result = tx.commit();
-    //println!("commit_result: {:#?}", commit_result);
-    return ();
-}
-// END ham_AM //
-
-// BEGIN ham_EN //
-fn insert_en_rows_batch(
-    mut conn: PooledConn,
-    en_records: Vec<data::Entity>,
-    _chunk_id: u32,
-    _tot_chunks: usize,
-    pb: &ProgressBar,
-) {
-    let mut tx = conn.start_transaction(TxOpts::default()).unwrap();
-    let result = tx.exec_batch(
-        "INSERT INTO ham_EN (\
-				`Record Type`, \
-				`Unique System Identifier`, \
-				`ULS File Number`, \
-				`EBF Number`, \
-				`Call Sign`, \
-				`Entity Type`, \
-				`Licensee ID`, \
-				`Entity Name`, \
-				`First Name`, \
-				`MI`, \
-				`Last Name`, \
-				`Suffix`, \
-				`Phone`, \
-				`Fax`, \
-				`Email`, \
-				`Street Address`, \
-				`City`, \
-				`State`, \
-				`Zip Code`, \
-				`PO Box`, \
-				`Attention Line`, \
-				`SGIN`, \
-				`FRN`, \
-				`Applicant Type Code`, \
-				`Status Code`, \
-				`Status Date` \
-				) \
-				VALUES (\
-				:record_type, \
-				:unique_system_identifier, \
-				:uls_file_num, \
-				:ebf_number, \
-				:call_sign, \
-				:entity_type, \
-				:licensee_id, \
-				:entity_name, \
-				:first_name, \
-				:mi, \
-				:last_name, \
-				:suffix, \
-				:phone, \
-				:fax, \
-				:email, \
-				:street_address, \
-				:city, \
-				:state, \
-				:zip_code, \
-				:po_box, \
-				:attention_line, \
-				:sgin, \
-				:frn, \
-				:applicant_type_code, \
-				:status_code, \
-				:status_date \
-				)",
-        en_records.iter().map(|p| {
-            pb.inc(1);
-            params! {
-            "record_type" => p.record_type.clone(),
-            "unique_system_identifier" => p.unique_system_identifier,
-            "uls_file_num" => p.uls_file_num.clone(),
-            "ebf_number" => p.ebf_number.clone(),
-            "call_sign" => p.call_sign.clone(),
-            "entity_type" => p.entity_type.clone(),
-            "licensee_id" => p.licensee_id.clone(),
-            "entity_name" => p.entity_name.clone(),
-            "first_name" => p.first_name.clone(),
-            "mi" => p.mi.clone(),
-            "last_name" => p.last_name.clone(),
-            "suffix" => p.suffix.clone(),
-            "phone" => p.phone.clone(),
-            "fax" => p.fax.clone(),
-            "email" => p.email.clone(),
-            "street_address" => p.street_address.clone(),
-            "city" => p.city.clone(),
-            "state" => p.state.clone(),
-            "zip_code" => p.zip_code.clone(),
-            "po_box" => p.po_box.clone(),
-            "attention_line" => p.attention_line.clone(),
-            "sgin" => p.sgin.clone(),
-            "frn" => p.frn.clone(),
-            "applicant_type_code" => p.applicant_type_code.clone(),
-            "status_code" => p.status_code.clone(),
-            "status_date" => p.status_date.clone(),
+        match value {
+            Value::NULL => out.extend_from_slice(b"\\N"),
+            Value::Bytes(bytes) => {
+                for byte in bytes {
+                    match byte {
+                        b'\\' => out.extend_from_slice(b"\\\\"),
+                        b'\t' => out.extend_from_slice(b"\\t"),
+                        b'\n' => out.extend_from_slice(b"\\n"),
+                        b'\r' => out.extend_from_slice(b"\\r"),
+                        _ => out.push(*byte),
+                    }
+                }
             }
-        }),
-    );
-    let result_value = match result {
-        Ok(_result_value) => {
-            tx.commit().unwrap();
+            Value::Int(value) => out.extend_from_slice(value.to_string().as_bytes()),
+            Value::UInt(value) => out.extend_from_slice(value.to_string().as_bytes()),
+            other => out.extend_from_slice(other.as_sql(true).trim_matches('\'').as_bytes()),
         }
-        Err(result_value) => {
-            println!("Error: {:#?}", result_value);
-            tx.rollback().unwrap();
+    }
+    out.push(b'\n');
+}
+// END row mappings //
+
+// BEGIN row hashes //
+/*
+ * Every MySQL row carries a SHA-256 of its text form in `Row Hash`, so sync_rows can tell which
+ * rows changed without comparing every column.
+ */
+pub const ROW_HASH_COLUMN: &str = "Row Hash";
+
+fn row_hash(values: &[Value]) -> String {
+    let mut text: Vec<u8> = Vec::new();
+    write_text_row(&mut text, values);
+    Sha256::digest(&text)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn with_row_hash(mut values: Vec<Value>) -> Vec<Value> {
+    let hash = row_hash(&values);
+    values.push(Value::from(hash));
+    values
+}
+
+pub fn hashed_values<T: SqlRow>(record: &T) -> Vec<Value> {
+    with_row_hash(record.values())
+}
+// END row hashes //
+
+// BEGIN retries //
+// Deadlocks, lock wait timeouts and dropped connections are worth another attempt.
+fn is_transient(why: &mysql::Error) -> bool {
+    match why {
+        mysql::Error::IoError(_) | mysql::Error::CodecError(_) => true,
+        mysql::Error::DriverError(driver_error) => matches!(
+            driver_error,
+            DriverError::ConnectTimeout | DriverError::CouldNotConnect(_) | DriverError::Timeout
+        ),
+        mysql::Error::MySqlError(mysql_error) => {
+            matches!(mysql_error.code, 1040 | 1205 | 1213 | 2006 | 2013)
         }
-    };
-    pb.finish();
-    //println!("Chunk {}/{} complete", chunk_id, tot_chunks);
-    return result_value;
-}
-
-fn split_en_rows(input_records: Vec<data::Entity>) -> Vec<Vec<data::Entity>> {
-    let mut retval: Vec<Vec<data::Entity>> = Vec::new();
-    let mut i = 0;
-    let mut n = 0;
-    let split_modulus = input_records.len() / 10;
-    for en_row in input_records {
-        if (n % split_modulus) == 0 {
-            let mut this_vec: Vec<data::Entity> = Vec::new();
-            this_vec.push(en_row);
-            retval.push(this_vec);
-            i = retval.len() - 1;
-        } else {
-            retval[i].push(en_row);
+        _ => false,
+    }
+}
+
+fn with_retries<R>(
+    max_retries: u32,
+    what: &str,
+    mut f: impl FnMut() -> mysql::Result<R>,
+) -> mysql::Result<R> {
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Err(why) if attempt < max_retries && is_transient(&why) => {
+                attempt += 1;
+                let delay = Duration::from_secs(1 << attempt.min(6));
+                println!(
+                    "{} failed ({}), retry {}/{} in {}s",
+                    what,
+                    why,
+                    attempt,
+                    max_retries,
+                    delay.as_secs()
+                );
+                thread::sleep(delay);
+            }
+            Err(why) => {
+                println!("{} failed permanently: {}", what, why);
+                return Err(why);
+            }
+            result => return result,
         }
-        n = n + 1;
     }
-    return retval;
+}
+// END retries //
+
+// BEGIN load summaries //
+pub struct LoadSummary {
+    pub table: &'static str,
+    pub rows: usize,
+    pub loaded: usize,
+    pub failed: usize,
+    // Rows dropped when the records were parsed, because a later row had the same primary key.
+    pub skipped: usize,
+    pub error: Option<String>,
+}
+
+impl LoadSummary {
+    pub fn is_complete(&self) -> bool {
+        self.failed == 0 && self.error.is_none()
+    }
 }
 
-fn do_en_drop(mut conn: mysql::PooledConn) -> mysql::Result<()> {
-    let mut tx = conn.start_transaction(TxOpts::default()).unwrap();
-    let result = tx.exec_drop("DELETE FROM ham_EN", ());
-    tx.commit().unwrap();
-    return result;
+impl fmt::Display for LoadSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} rows, {} loaded, {} failed",
+            self.table, self.rows, self.loaded, self.failed
+        )?;
+        if self.skipped > 0 {
+            write!(f, ", {} skipped as duplicates", self.skipped)?;
+        }
+        if let Some(error) = &self.error {
+            write!(f, " ({})", error)?;
+        }
+        Ok(())
+    }
 }
+// END load summaries //
 
-pub fn insert_en_rows(sql_url: &str, en_records: Vec<data::Entity>) {
-    let pool = Pool::new(sql_url).unwrap();
+// BEGIN inserts //
+// One connection per loader thread, up to max_connections.
+fn connect(mysql_config: &MySQLConfig) -> Result<Pool, Box<dyn std::error::Error>> {
+    let max_connections = mysql_config.max_connections.max(1);
+    let min_connections = mysql_config.threads.clamp(1, max_connections);
+    let constraints = PoolConstraints::new(min_connections, max_connections)
+        .ok_or("max_connections must be at least 1")?;
+    let opts = OptsBuilder::from_opts(Opts::from_url(&mysql_config.sql_url)?)
+        .pool_opts(PoolOpts::default().with_constraints(constraints));
+    Ok(Pool::new(opts)?)
+}
 
-    println!("Splitting rows!");
-    let en_records_split = split_en_rows(en_records);
-    println!("Rows split into {} chunks!", en_records_split.len());
+fn insert_statement<T: SqlRow>(table: &str) -> String {
+    let columns: Vec<String> = T::COLUMNS
+        .iter()
+        .map(|(_field, column)| column)
+        .chain(std::iter::once(&ROW_HASH_COLUMN))
+        .map(|column| format!("`{}`", column))
+        .collect();
+    let placeholders: Vec<&str> = columns.iter().map(|_| "?").collect();
+    format!(
+        "INSERT INTO `{}` ({}) VALUES ({})",
+        table,
+        columns.join(", "),
+        placeholders.join(", ")
+    )
+}
 
-    println!("Dropping ham_EN!");
-    do_en_drop(pool.get_conn().unwrap()).unwrap();
+fn load_data_statement<T: SqlRow>(table: &str) -> String {
+    let columns: Vec<String> = T::COLUMNS
+        .iter()
+        .map(|(_field, column)| column)
+        .chain(std::iter::once(&ROW_HASH_COLUMN))
+        .map(|column| format!("`{}`", column))
+        .collect();
+    format!(
+        "LOAD DATA LOCAL INFILE '{0}' INTO TABLE `{0}` CHARACTER SET utf8mb4 \
+         FIELDS TERMINATED BY '\\t' ESCAPED BY '\\\\' LINES TERMINATED BY '\\n' ({1})",
+        table,
+        columns.join(", ")
+    )
+}
 
-    println!("Inserting rows!");
-    let multiprogress_bar = Arc::new(MultiProgress::new());
-    let tpool = ThreadPoolBuilder::new().num_threads(10).build().unwrap();
-    let mut this_chunk = 0;
-    let tot_chunks = en_records_split.len();
-    let mp_clone = multiprogress_bar.clone();
-    println!("Entering In Place Scope!");
-    tpool.in_place_scope(move |s| {
-        for en_chunk in en_records_split {
-            let multiprogress_clone = multiprogress_bar.clone();
-            let conn = pool.get_conn().unwrap();
-            this_chunk = this_chunk + 1;
-            //println!("Starting chunk {}/{}", this_chunk, tot_chunks);
-            s.spawn(move |_| {
-                let pb =
-                    multiprogress_clone.add(ProgressBar::new(en_chunk.len().try_into().unwrap()));
-                insert_en_rows_batch(conn, en_chunk, this_chunk, tot_chunks, &pb);
-            });
-        }
-    });
-    let _ = mp_clone.clear();
-    println!("In Place Scope exited");
+/*
+ * Streams the chunk to the server as one tab-separated buffer.  With LOCAL, rows the server
+ * rejects only become warnings, so anything short of every row loaded counts as a failure.
+ */
+fn load_data_batch<T: SqlRow>(
+    tx: &mut Transaction,
+    table: &str,
+    records: &[T],
+    pb: &ProgressBar,
+) -> Result<(), String> {
+    let mut buffer: Vec<u8> = Vec::new();
+    for record in records {
+        write_text_row(&mut buffer, &hashed_values(record));
+        pb.inc(1);
+    }
+    tx.set_local_infile_handler(Some(LocalInfileHandler::new(move |_file_name, writer| {
+        writer.write_all(&buffer)
+    })));
+    let result = tx.query_drop(load_data_statement::<T>(table));
+    tx.set_local_infile_handler(None);
+    result.map_err(|why| why.to_string())?;
 
-    //let _ = tx.commit();
-    //let commit_result = tx.commit();
-    //println!("commit_result: {:#?}", commit_result);
-    return ();
+    let loaded = tx.affected_rows();
+    if loaded != records.len() as u64 {
+        return Err(format!(
+            "loaded {} of {} rows ({} warnings)",
+            loaded,
+            records.len(),
+            tx.warnings()
+        ));
+    }
+    Ok(())
 }
-// END ham_EN //
 
-// BEGIN ham_HD //
-fn insert_hd_rows_batch(
-    mut conn: PooledConn,
-    hd_records: Vec<data::ApplicationLicenseHeader>,
-    _chunk_id: u32,
-    _tot_chunks: usize,
+fn insert_rows_batch<T: SqlRow>(
+    mut conn: mysql::PooledConn,
+    records: &[T],
+    bulk_load: bool,
+    chunk_id: u32,
+    tot_chunks: usize,
     pb: &ProgressBar,
-) {
-    let mut tx = conn.start_transaction(TxOpts::default()).unwrap();
-    let result = tx.exec_batch(
-        "INSERT INTO ham_HD (\
-				`Record Type`, \
-				`Unique System Identifier`, \
-				`ULS File Number`, \
-				`EBF Number`, \
-				`Call Sign`, \
-				`License Status`, \
-				`Radio Service Code`, \
-				`Grant Date`, \
-				`Expired Date`, \
-				`Cancellation Date`, \
-				`Eligibility Rule Num`, \
-				`Reserved`, \
-				`Alien`, \
-				`Alien Government`, \
-				`Alien Corporation`, \
-				`Alien Officer`, \
-				`Alien Control`, \
-				`Revoked`, \
-				`Convicted`, \
-				`Adjudged`, \
-				`Involved Reserved`, \
-				`Common Carrier`, \
-				`Non Common Carrier`, \
-				`Private Comm`, \
-				`Fixed`, \
-				`Mobile`, \
-				`Radiolocation`, \
-				`Satellite`, \
-				`Developmental or STA or Demonstration`, \
-				`Interconnected Service`, \
-				`Certifier First Name`, \
-				`Certifier MI`, \
-				`Certifier Last Name`, \
-				`Certifier Suffix`, \
-				`Certifier Title`, \
-				`Gender`, \
-				`African American`, \
-				`Native American`, \
-				`Hawaiian`, \
-				`Asian`, \
-				`White`, \
-				`Ethnicity`, \
-				`Effective Date`, \
-				`Last Action Date`, \
-				`Auction ID`, \
-				`Broadcast Services - Regulatory Status`, \
-				`Band Manager`, \
-				`Broadcast Services - Type of Radio Service`, \
-				`Alien Ruling`, \
-				`Licensee Name Change`, \
-				`Whitespace Ind`, \
-				`Additional Cert Choice`, \
-				`Additional Cert Answer`, \
-				`Discontinuation Ind`, \
-				`Regulatory Compliance Ind`, \
-				`Eligibility Cert 900`, \
-				`Transition Plan Cert 900`, \
-				`Return Spectrum Cert 900`, \
-				`Payment Cert 900` \
-				) \
-				VALUES (\
-				:record_type, \
-				:unique_system_identifier, \
-				:uls_file_num, \
-				:ebf_number, \
-				:call_sign, \
-				:license_status, \
-				:radio_service_code, \
-				:grant_date, \
-				:expired_date, \
-				:cancellation_date, \
-				:eligibility_rule_num, \
-				:applicant_type_code_reserved, \
-				:alien, \
-				:alien_government, \
-				:alien_corporation, \
-				:alien_officer, \
-				:alien_control, \
-				:revoked, \
-				:convicted, \
-				:adjudged, \
-				:involved_reserved, \
-				:common_carrier, \
-				:non_common_carrier, \
-				:private_comm, \
-				:fixed, \
-				:mobile, \
-				:radiolocation, \
-				:satellite, \
-				:developmental_or_sta, \
-				:interconnected_service, \
-				:certifier_first_name, \
-				:certifier_mi, \
-				:certifier_last_name, \
-				:certifier_suffix, \
-				:certifier_title, \
-				:gender, \
-				:african_american, \
-				:native_american, \
-				:hawaiian, \
-				:asian, \
-				:white, \
-				:ethnicity, \
-				:effective_date, \
-				:last_action_date, \
-				:auction_id, \
-				:reg_stat_broad_serv, \
-				:band_manager, \
-				:type_serv_broad_serv, \
-				:alien_ruling, \
-				:licensee_name_change, \
-				:whitespace_ind, \
-				:additional_cert_choice, \
-				:additional_cert_answer, \
-				:discontinuation_ind, \
-				:regulatory_compliance_ind, \
-				:eligibility_cert_900, \
-				:transition_plan_cert_900, \
-				:return_spectrum_cert_900, \
-				:payment_cert_900 \
-				)",
-        hd_records.iter().map(|p| {
-            pb.inc(1);
-            params! {
-            "record_type" => p.record_type.clone(),
-            "unique_system_identifier" => p.unique_system_identifier,
-            "uls_file_num" => p.uls_file_num.clone(),
-            "ebf_number" => p.ebf_number.clone(),
-            "call_sign" => p.call_sign.clone(),
-            "license_status" => p.license_status.clone(),
-            "radio_service_code" => p.radio_service_code.clone(),
-            "grant_date" => p.grant_date.clone(),
-            "expired_date" => p.expired_date.clone(),
-            "cancellation_date" => p.cancellation_date.clone(),
-            "eligibility_rule_num" => p.eligibility_rule_num.clone(),
-            "applicant_type_code_reserved" => p.applicant_type_code_reserved.clone(),
-            "alien" => p.alien.clone(),
-            "alien_government" => p.alien_government.clone(),
-            "alien_corporation" => p.alien_corporation.clone(),
-            "alien_officer" => p.alien_officer.clone(),
-            "alien_control" => p.alien_control.clone(),
-            "revoked" => p.revoked.clone(),
-            "convicted" => p.convicted.clone(),
-            "adjudged" => p.adjudged.clone(),
-            "involved_reserved" => p.involved_reserved.clone(),
-            "common_carrier" => p.common_carrier.clone(),
-            "non_common_carrier" => p.non_common_carrier.clone(),
-            "private_comm" => p.private_comm.clone(),
-            "fixed" => p.fixed.clone(),
-            "mobile" => p.mobile.clone(),
-            "radiolocation" => p.radiolocation.clone(),
-            "satellite" => p.satellite.clone(),
-            "developmental_or_sta" => p.developmental_or_sta.clone(),
-            "interconnected_service" => p.interconnected_service.clone(),
-            "certifier_first_name" => p.certifier_first_name.clone(),
-            "certifier_mi" => p.certifier_mi.clone(),
-            "certifier_last_name" => p.certifier_last_name.clone(),
-            "certifier_suffix" => p.certifier_suffix.clone(),
-            "certifier_title" => p.certifier_title.clone(),
-            "gender" => p.gender.clone(),
-            "african_american" => p.african_american.clone(),
-            "native_american" => p.native_american.clone(),
-            "hawaiian" => p.hawaiian.clone(),
-            "asian" => p.asian.clone(),
-            "white" => p.white.clone(),
-            "ethnicity" => p.ethnicity.clone(),
-            "effective_date" => p.effective_date.clone(),
-            "last_action_date" => p.last_action_date.clone(),
-            "auction_id" => p.auction_id.clone(),
-            "reg_stat_broad_serv" => p.reg_stat_broad_serv.clone(),
-            "band_manager" => p.band_manager.clone(),
-            "type_serv_broad_serv" => p.type_serv_broad_serv.clone(),
-            "alien_ruling" => p.alien_ruling.clone(),
-            "licensee_name_change" => p.licensee_name_change.clone(),
-            "whitespace_ind" => p.whitespace_ind.clone(),
-            "additional_cert_choice" => p.additional_cert_choice.clone(),
-            "additional_cert_answer" => p.additional_cert_answer.clone(),
-            "discontinuation_ind" => p.discontinuation_ind.clone(),
-            "regulatory_compliance_ind" => p.regulatory_compliance_ind.clone(),
-            "eligibility_cert_900" => p.eligibility_cert_900.clone(),
-            "transition_plan_cert_900" => p.transition_plan_cert_900.clone(),
-            "return_spectrum_cert_900" => p.return_spectrum_cert_900.clone(),
-            "payment_cert_900" => p.payment_cert_900.clone(),
+) -> mysql::Result<()> {
+    let table = format!("{}_staging", T::TABLE);
+    let mut tx = conn.start_transaction(TxOpts::default())?;
+
+    let mut loaded = false;
+    if bulk_load {
+        tx.query_drop("SAVEPOINT load_data")?;
+        match load_data_batch(&mut tx, &table, records, pb) {
+            Ok(()) => loaded = true,
+            Err(why) => {
+                println!(
+                    "LOAD DATA failed for {} chunk {}, falling back to INSERT: {}",
+                    T::TABLE,
+                    chunk_id,
+                    why
+                );
+                tx.query_drop("ROLLBACK TO SAVEPOINT load_data")?;
+                pb.set_position(0);
             }
-        }),
-    );
-    let result_value = match result {
-        Ok(_result_value) => {
-            //println!("{:#?}", result_value);
-            tx.commit().unwrap();
         }
+    }
+
+    let result = if loaded {
+        Ok(())
+    } else {
+        tx.exec_batch(
+            insert_statement::<T>(&table),
+            records.iter().map(|p| {
+                pb.inc(1);
+                Params::Positional(hashed_values(p))
+            }),
+        )
+    };
+    let result = match result {
+        Ok(_result_value) => tx.commit(),
         Err(result_value) => {
             println!("Error: {:#?}", result_value);
-            tx.rollback().unwrap();
+            tx.rollback()?;
+            Err(result_value)
         }
     };
-    pb.finish();
-    //println!("Chunk {}/{} complete", chunk_id, tot_chunks);
-    return result_value;
-}
-
-fn split_hd_rows(
-    input_records: Vec<data::ApplicationLicenseHeader>,
-) -> Vec<Vec<data::ApplicationLicenseHeader>> {
-    let mut retval: Vec<Vec<data::ApplicationLicenseHeader>> = Vec::new();
-    let mut i = 0;
-    let mut n = 0;
-    let split_modulus = input_records.len() / 10;
-    for hd_row in input_records {
-        if (n % split_modulus) == 0 {
-            let mut this_vec: Vec<data::ApplicationLicenseHeader> = Vec::new();
-            this_vec.push(hd_row);
-            retval.push(this_vec);
-            i = retval.len() - 1;
-        } else {
-            retval[i].push(hd_row);
-        }
-        n = n + 1;
+    if result.is_ok() {
+        pb.finish();
+        println!("{} chunk {}/{} complete", T::TABLE, chunk_id, tot_chunks);
     }
-    return retval;
-}
-
-fn do_hd_drop(mut conn: mysql::PooledConn) -> mysql::Result<()> {
-    let mut tx = conn.start_transaction(TxOpts::default()).unwrap();
-    let result = tx.exec_drop("DELETE FROM ham_HD", ());
-    tx.commit().unwrap();
-    return result;
+    result
 }
 
-pub fn insert_hd_rows(sql_url: &str, hd_records: Vec<data::ApplicationLicenseHeader>) {
-    let pool = Pool::new(sql_url).unwrap();
+pub fn insert_rows<T: SqlRow>(
+    mysql_config: &MySQLConfig,
+    records: &[T],
+    skipped: usize,
+) -> Result<LoadSummary, Box<dyn std::error::Error>> {
+    let pool = connect(mysql_config)?;
+    let bulk_load = mysql_config.bulk_load;
+    let max_retries = mysql_config.max_retries;
 
-    println!("Splitting rows!");
-    let hd_records_split = split_hd_rows(hd_records);
-    println!("Rows split into {} chunks!", hd_records_split.len());
+    // Chunks of at most batch_size rows, one transaction each.
+    let records_split: Vec<&[T]> = records.chunks(mysql_config.batch_size.max(1)).collect();
+    println!("Rows split into {} chunks", records_split.len());
 
-    println!("Dropping ham_HD!");
-    do_hd_drop(pool.get_conn().unwrap()).unwrap();
+    println!("Creating {}_staging", T::TABLE);
+    create_staging_table(&mut pool.get_conn()?, T::TABLE)?;
 
-    println!("Inserting rows!");
+    println!("Inserting rows");
     let multiprogress_bar = Arc::new(MultiProgress::new());
-    let tpool = ThreadPoolBuilder::new().num_threads(10).build().unwrap();
+    let tpool = ThreadPoolBuilder::new()
+        .num_threads(mysql_config.threads.max(1))
+        .build()?;
     let mut this_chunk = 0;
-    let tot_chunks = hd_records_split.len();
-    println!("Entering In Place Scope!");
+    let tot_chunks = records_split.len();
+    let failed_chunks = AtomicUsize::new(0);
+    let loaded_rows = AtomicUsize::new(0);
+    let failed_rows = AtomicUsize::new(0);
     let mp_clone = multiprogress_bar.clone();
-    tpool.in_place_scope(move |s| {
-        for hd_chunk in hd_records_split {
+    println!("Entering In Place Scope");
+    tpool.in_place_scope(|s| {
+        for chunk in records_split {
+            this_chunk += 1;
+            let pool = &pool;
             let multiprogress_clone = multiprogress_bar.clone();
-            let conn = pool.get_conn().unwrap();
-            this_chunk = this_chunk + 1;
+            let failed_chunks = &failed_chunks;
+            let loaded_rows = &loaded_rows;
+            let failed_rows = &failed_rows;
             s.spawn(move |_| {
-                let pb =
-                    multiprogress_clone.add(ProgressBar::new(hd_chunk.len().try_into().unwrap()));
-                insert_hd_rows_batch(conn, hd_chunk, this_chunk, tot_chunks, &pb);
+                let pb = multiprogress_clone.add(ProgressBar::new(chunk.len().try_into().unwrap()));
+                let what = format!("{} chunk {}/{}", T::TABLE, this_chunk, tot_chunks);
+                let result = with_retries(max_retries, &what, || {
+                    pb.set_position(0);
+                    let conn = pool.get_conn()?;
+                    insert_rows_batch(conn, chunk, bulk_load, this_chunk, tot_chunks, &pb)
+                });
+                if result.is_ok() {
+                    multiprogress_clone.remove(&pb);
+                    loaded_rows.fetch_add(chunk.len(), Ordering::SeqCst);
+                } else {
+                    pb.abandon();
+                    failed_chunks.fetch_add(1, Ordering::SeqCst);
+                    failed_rows.fetch_add(chunk.len(), Ordering::SeqCst);
+                }
             });
         }
     });
     let _ = mp_clone.clear();
     println!("In Place Scope exited");
 
-    //let _ = tx.commit();
-    //let commit_result = tx.commit();
-    //println!("commit_result: {:#?}", commit_result);
-    return ();
+    let result = finish_staging_table(
+        &mut pool.get_conn()?,
+        T::TABLE,
+        failed_chunks.into_inner(),
+        tot_chunks,
+    );
+    Ok(LoadSummary {
+        table: T::TABLE,
+        rows: records.len() + skipped,
+        loaded: loaded_rows.into_inner(),
+        failed: failed_rows.into_inner(),
+        skipped,
+        error: result.err().map(|why| why.to_string()),
+    })
+}
+// END inserts //
+
+// BEGIN primary keys //
+fn key_positions<T: SqlRow>() -> Vec<usize> {
+    T::KEY
+        .iter()
+        .map(|key| {
+            T::COLUMNS
+                .iter()
+                .position(|(_field, column)| column == key)
+                .unwrap()
+        })
+        .collect()
+}
+
+fn key_text(values: &[Value], key_positions: &[usize]) -> String {
+    let key_values: Vec<Value> = key_positions.iter().map(|i| values[*i].clone()).collect();
+    let mut text: Vec<u8> = Vec::new();
+    write_text_row(&mut text, &key_values);
+    String::from_utf8_lossy(&text).into_owned()
+}
+// END primary keys //
+
+// BEGIN sync //
+/*
+ * Incremental load: compares the parsed rows with the live table by primary key and Row Hash,
+ * then inserts, updates and deletes only the rows that differ, in a single transaction.
+ */
+pub fn sync_rows<T: SqlRow>(
+    mysql_config: &MySQLConfig,
+    records: &[T],
+    skipped: usize,
+) -> Result<LoadSummary, Box<dyn std::error::Error>> {
+    let pool = connect(mysql_config)?;
+    let key_positions = key_positions::<T>();
+    let key_only_positions: Vec<usize> = (0..T::KEY.len()).collect();
+    let key_columns: Vec<String> = T::KEY.iter().map(|key| format!("`{}`", key)).collect();
+    let key_condition: Vec<String> = key_columns
+        .iter()
+        .map(|key| format!("{} = ?", key))
+        .collect();
+
+    println!("Reading {} row hashes", T::TABLE);
+    let mut conn = pool.get_conn()?;
+    let mut existing: HashMap<String, (Vec<Value>, String)> = HashMap::new();
+    for row in conn.query_iter(format!(
+        "SELECT {}, `{}` FROM `{}`",
+        key_columns.join(", "),
+        ROW_HASH_COLUMN,
+        T::TABLE
+    ))? {
+        let mut values = row?.unwrap();
+        let hash = match values.pop() {
+            Some(Value::Bytes(bytes)) => String::from_utf8_lossy(&bytes).into_owned(),
+            _ => String::new(),
+        };
+        existing.insert(key_text(&values, &key_only_positions), (values, hash));
+    }
+
+    println!("Comparing {} rows", T::TABLE);
+    let mut inserts: Vec<Vec<Value>> = Vec::new();
+    let mut updates: Vec<Vec<Value>> = Vec::new();
+    let mut unchanged = 0;
+    for record in records {
+        let values = hashed_values(record);
+        let key = key_text(&values, &key_positions);
+        match existing.remove(&key) {
+            None => inserts.push(values),
+            Some((key_values, hash)) => {
+                if values.last() == Some(&Value::from(hash)) {
+                    unchanged += 1;
+                } else {
+                    let mut params = values;
+                    params.extend(key_values);
+                    updates.push(params);
+                }
+            }
+        }
+    }
+    let deletes: Vec<Vec<Value>> = existing
+        .into_values()
+        .map(|(key_values, _hash)| key_values)
+        .collect();
+
+    let set_columns: Vec<String> = T::COLUMNS
+        .iter()
+        .map(|(_field, column)| column)
+        .chain(std::iter::once(&ROW_HASH_COLUMN))
+        .map(|column| format!("`{}` = ?", column))
+        .collect();
+    let what = format!("{} sync", T::TABLE);
+    with_retries(mysql_config.max_retries, &what, || {
+        let mut conn = pool.get_conn()?;
+        let mut tx = conn.start_transaction(TxOpts::default())?;
+        tx.exec_batch(
+            format!(
+                "DELETE FROM `{}` WHERE {}",
+                T::TABLE,
+                key_condition.join(" AND ")
+            ),
+            deletes
+                .iter()
+                .map(|key_values| Params::Positional(key_values.clone())),
+        )?;
+        tx.exec_batch(
+            format!(
+                "UPDATE `{}` SET {} WHERE {}",
+                T::TABLE,
+                set_columns.join(", "),
+                key_condition.join(" AND ")
+            ),
+            updates
+                .iter()
+                .map(|params| Params::Positional(params.clone())),
+        )?;
+        tx.exec_batch(
+            insert_statement::<T>(T::TABLE),
+            inserts
+                .iter()
+                .map(|values| Params::Positional(values.clone())),
+        )?;
+        tx.commit()
+    })?;
+
+    println!(
+        "{}: {} inserted, {} updated, {} deleted, {} unchanged",
+        T::TABLE,
+        inserts.len(),
+        updates.len(),
+        deletes.len(),
+        unchanged
+    );
+    Ok(LoadSummary {
+        table: T::TABLE,
+        rows: records.len() + skipped,
+        loaded: records.len(),
+        failed: 0,
+        skipped,
+        error: None,
+    })
+}
+
+/*
+ * Loads the rows with the configured mode.  Errors are reported in the summary, never dropped.
+ * skipped is the number of duplicates dropped when the records were parsed.
+ */
+pub fn load_rows<T: SqlRow>(
+    mysql_config: &MySQLConfig,
+    records: &[T],
+    skipped: usize,
+) -> LoadSummary {
+    let result = match mysql_config.load_mode {
+        LoadMode::Full => insert_rows(mysql_config, records, skipped),
+        LoadMode::Sync => sync_rows(mysql_config, records, skipped),
+    };
+    result.unwrap_or_else(|why| LoadSummary {
+        table: T::TABLE,
+        rows: records.len() + skipped,
+        loaded: 0,
+        failed: records.len(),
+        skipped,
+        error: Some(why.to_string()),
+    })
+}
+// END sync //
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JoinMode;
+    use crate::fixtures;
+
+    fn named_values<T: SqlRow>(record: &T) -> HashMap<&'static str, Value> {
+        T::COLUMNS
+            .iter()
+            .map(|(_field, column)| *column)
+            .zip(record.values())
+            .collect()
+    }
+
+    #[test]
+    fn license_values_match_columns() {
+        for license in fixtures::licenses(&JoinMode::Left) {
+            assert_eq!(license.values().len(), crate::License::COLUMNS.len());
+        }
+    }
+
+    #[test]
+    fn license_columns_come_from_the_tables() {
+        let mut columns: Vec<&str> = crate::License::COLUMNS
+            .iter()
+            .map(|(_field, column)| *column)
+            .collect();
+        let count = columns.len();
+        columns.sort();
+        columns.dedup();
+        assert_eq!(columns.len(), count, "duplicate license column");
+
+        let license = fixtures::license();
+        let mut source = named_values(license.header.as_ref().unwrap());
+        source.extend(named_values(license.licensee.as_ref().unwrap()));
+        source.extend(named_values(license.amateur.as_ref().unwrap()));
+        for ((_field, column), value) in crate::License::COLUMNS.iter().zip(license.values()) {
+            assert_eq!(source.get(column), Some(&value), "{}", column);
+        }
+    }
+
+    #[test]
+    fn summary_reports_skipped_rows() {
+        let mut summary = LoadSummary {
+            table: "ham_EN",
+            rows: 4,
+            loaded: 4,
+            failed: 0,
+            skipped: 0,
+            error: None,
+        };
+        assert_eq!(summary.to_string(), "ham_EN: 4 rows, 4 loaded, 0 failed");
+        summary.loaded = 3;
+        summary.skipped = 1;
+        assert_eq!(
+            summary.to_string(),
+            "ham_EN: 4 rows, 3 loaded, 0 failed, 1 skipped as duplicates"
+        );
+        assert!(summary.is_complete());
+    }
+
+    #[test]
+    fn failed_load_still_reports_skipped_rows() {
+        let mysql_config: MySQLConfig = serde_yaml::from_str("sql_url: not a url").unwrap();
+        let entity = crate::fixtures::fcc_db().entity;
+        let summary = load_rows(&mysql_config, &entity, 2);
+        assert_eq!((summary.rows, summary.failed, summary.skipped), (6, 4, 2));
+        assert!(!summary.is_complete());
+        assert!(summary
+            .to_string()
+            .starts_with("ham_EN: 6 rows, 0 loaded, 4 failed, 2 skipped as duplicates ("));
+    }
+
+    #[test]
+    fn license_fields_keep_their_names() {
+        let tables = [
+            <data::Amateur as SqlRow>::COLUMNS,
+            <data::Entity as SqlRow>::COLUMNS,
+            <data::ApplicationLicenseHeader as SqlRow>::COLUMNS,
+        ];
+        for column in crate::License::COLUMNS {
+            assert!(
+                tables.iter().any(|columns| columns.contains(column)),
+                "{:?}",
+                column
+            );
+        }
+    }
 }
-// END ham_HD //
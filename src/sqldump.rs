@@ -0,0 +1,194 @@
+use indicatif::ProgressBar;
+use mysql::Value;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::config::LicenseOutput;
+use crate::data;
+use crate::profile::Profile;
+use crate::schema;
+use crate::sql::{self, SqlRow};
+
+/*
+ * Offline SQL dump of the ham_* tables.
+ *
+ * Writes a self-contained .sql file for machines that cannot reach the database: it drops and
+ * recreates every table at the current schema version, then loads the same rows, row hashes
+ * included, that the MySQL loader would, with ham_license cut down to the output profile's
+ * columns and the identifier it is keyed on.  Apply it with `mysql fcc < fccdb.sql`.
+ */
+
+// A new INSERT is started once the current one reaches this many bytes, well under the
+// smallest default max_allowed_packet.
+const INSERT_STATEMENT_SIZE: usize = 1 << 20;
+
+fn write_table<T: SqlRow>(
+    out: &mut impl Write,
+    columns: &[(&str, &str)],
+    records: &[T],
+    project: impl Fn(Vec<Value>) -> Vec<Value>,
+) -> std::io::Result<()> {
+    println!("Writing {} SQL dump", T::TABLE);
+    let columns: Vec<String> = columns
+        .iter()
+        .map(|(_field, column)| column)
+        .chain(std::iter::once(&sql::ROW_HASH_COLUMN))
+        .map(|column| format!("`{}`", column))
+        .collect();
+    let insert = format!(
+        "INSERT INTO `{}` ({}) VALUES\n",
+        T::TABLE,
+        columns.join(", ")
+    );
+
+    writeln!(out, "\n-- {}: {} rows", T::TABLE, records.len())?;
+    writeln!(out, "START TRANSACTION;")?;
+    let pb = ProgressBar::new(records.len().try_into().unwrap());
+    let mut statement = String::new();
+    for record in records {
+        let values: Vec<String> = sql::with_row_hash(project(record.values()))
+            .iter()
+            .map(|value| value.as_sql(false))
+            .collect();
+        statement.push_str(if statement.is_empty() { &insert } else { ",\n" });
+        statement.push('(');
+        statement.push_str(&values.join(","));
+        statement.push(')');
+        if statement.len() >= INSERT_STATEMENT_SIZE {
+            writeln!(out, "{};", statement)?;
+            statement.clear();
+        }
+        pb.inc(1);
+    }
+    if !statement.is_empty() {
+        writeln!(out, "{};", statement)?;
+    }
+    writeln!(out, "COMMIT;")?;
+    pb.finish();
+    Ok(())
+}
+
+pub fn write_dump(
+    filename: &str,
+    license_output: &LicenseOutput,
+    amateur: &[data::Amateur],
+    entity: &[data::Entity],
+    application_license_header: &[data::ApplicationLicenseHeader],
+    profile: &Profile,
+    licenses: &[crate::License],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_filename = format!("{}.tmp", filename);
+    let mut out = BufWriter::new(File::create(&tmp_filename)?);
+
+    writeln!(out, "-- FCC ULS amateur license database")?;
+    writeln!(out, "SET NAMES utf8mb4;")?;
+    // ham_license may be a table or a view; DROP TABLE IF EXISTS only warns about a view.
+    for table in [
+        "ham_license",
+        "ham_AM",
+        "ham_EN",
+        "ham_HD",
+        "ham_schema_version",
+    ] {
+        writeln!(out, "DROP TABLE IF EXISTS `{}`;", table)?;
+    }
+    writeln!(out, "DROP VIEW IF EXISTS `ham_license`;")?;
+    let profile = profile.keyed();
+    let license_columns = profile.columns();
+    let column_names: Vec<&str> = license_columns
+        .iter()
+        .map(|(_field, column)| *column)
+        .collect();
+    for statement in schema::schema_statements(license_output, &column_names) {
+        writeln!(out, "{};", statement)?;
+    }
+
+    write_table(&mut out, data::Amateur::COLUMNS, amateur, |values| values)?;
+    write_table(&mut out, data::Entity::COLUMNS, entity, |values| values)?;
+    write_table(
+        &mut out,
+        data::ApplicationLicenseHeader::COLUMNS,
+        application_license_header,
+        |values| values,
+    )?;
+    if *license_output == LicenseOutput::Table {
+        write_table(&mut out, &license_columns, licenses, |values| {
+            profile.project(values)
+        })?;
+    }
+
+    out.into_inner()?.sync_all()?;
+    std::fs::rename(&tmp_filename, filename)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JoinMode;
+    use crate::fixtures;
+    use std::collections::HashMap;
+
+    #[test]
+    fn dump_builds_the_schema_and_escapes_values() {
+        let directory = tempfile::tempdir().unwrap();
+        let filename = directory.path().join("fccdb.sql");
+        let filename = filename.to_str().unwrap();
+        let en = fixtures::EN[0].replace("|Last1|", r"|O'Brien\|");
+        let fcc_db = fixtures::parse_lines(fixtures::AM, &[&en], fixtures::HD);
+        let licenses: Vec<crate::License> =
+            crate::iter_licenses(&fcc_db, &JoinMode::Inner).collect();
+        let profile = Profile::named("callbook", &HashMap::new()).unwrap();
+        write_dump(
+            filename,
+            &LicenseOutput::Table,
+            &fcc_db.amateur,
+            &fcc_db.entity,
+            &fcc_db.application_license_header,
+            &profile,
+            &licenses,
+        )
+        .unwrap();
+        let dump = std::fs::read_to_string(filename).unwrap();
+
+        assert!(dump.starts_with(
+            "-- FCC ULS amateur license database\n\
+             SET NAMES utf8mb4;\n\
+             DROP TABLE IF EXISTS `ham_license`;\n"
+        ));
+        let statements: Vec<String> = dump.lines().map(str::to_string).collect();
+        for statement in schema::schema_statements(
+            &LicenseOutput::Table,
+            &profile
+                .keyed()
+                .columns()
+                .iter()
+                .map(|(_field, column)| *column)
+                .collect::<Vec<&str>>(),
+        ) {
+            assert!(
+                statements.contains(&format!("{};", statement)),
+                "{}",
+                statement
+            );
+        }
+        let version = format!(
+            "INSERT INTO ham_schema_version (`version`) VALUES ({});",
+            schema::MIGRATIONS.last().unwrap().0
+        );
+        assert!(statements.contains(&version));
+        // The schema comes before any rows.
+        let last_ddl = statements.iter().position(|line| *line == version).unwrap();
+        let first_row = statements
+            .iter()
+            .position(|line| line.starts_with("INSERT INTO `ham_"))
+            .unwrap();
+        assert!(last_ddl < first_row);
+
+        assert!(dump.contains(r"'O\'Brien\\'"));
+        assert!(
+            dump.contains("INSERT INTO `ham_license` (`Unique System Identifier`, `Call Sign`,")
+        );
+        assert!(!std::path::Path::new(&format!("{}.tmp", filename)).exists());
+    }
+}
@@ -0,0 +1,217 @@
+use indicatif::ProgressBar;
+use rusqlite::{params_from_iter, Connection, Transaction};
+
+use crate::data;
+use crate::schema;
+use crate::sql::{self, ColumnType, SqlRow};
+
+/*
+ * Single-file SQLite output.
+ *
+ * Writes ham_AM, ham_EN and ham_HD with the same columns the MySQL loader uses, indexes on
+ * callsign and FRN, and a ham_license view joining each license to its licensee entity.  The
+ * database is built under a temporary name and renamed into place when complete.
+ */
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+fn to_sqlite(value: mysql::Value) -> rusqlite::types::Value {
+    match value {
+        mysql::Value::NULL => rusqlite::types::Value::Null,
+        mysql::Value::Bytes(bytes) => {
+            rusqlite::types::Value::Text(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        mysql::Value::Int(value) => rusqlite::types::Value::Integer(value),
+        mysql::Value::UInt(value) => rusqlite::types::Value::Integer(value as i64),
+        other => rusqlite::types::Value::Text(other.as_sql(true).trim_matches('\'').to_string()),
+    }
+}
+
+fn write_table<T: SqlRow>(tx: &Transaction, records: &[T]) -> rusqlite::Result<()> {
+    let mut definitions: Vec<String> = T::COLUMNS
+        .iter()
+        .map(|(_field, column)| {
+            let column_type = match sql::column_type(column) {
+                ColumnType::Integer => "INTEGER NOT NULL",
+                ColumnType::NullableInteger => "INTEGER",
+                ColumnType::Text => "TEXT NOT NULL",
+            };
+            format!("{} {}", quote(column), column_type)
+        })
+        .collect();
+    let key: Vec<String> = T::KEY.iter().map(|column| quote(column)).collect();
+    definitions.push(format!("PRIMARY KEY ({})", key.join(", ")));
+    tx.execute_batch(&format!(
+        "CREATE TABLE {} ({})",
+        quote(T::TABLE),
+        definitions.join(", ")
+    ))?;
+
+    println!("Writing {} SQLite", T::TABLE);
+    let columns: Vec<String> = T::COLUMNS
+        .iter()
+        .map(|(_field, column)| quote(column))
+        .collect();
+    let placeholders: Vec<&str> = T::COLUMNS.iter().map(|_| "?").collect();
+    let mut statement = tx.prepare(&format!(
+        "INSERT INTO {} ({}) VALUES ({})",
+        quote(T::TABLE),
+        columns.join(", "),
+        placeholders.join(", ")
+    ))?;
+    let pb = ProgressBar::new(records.len().try_into().unwrap());
+    for record in records {
+        statement.execute(params_from_iter(record.values().into_iter().map(to_sqlite)))?;
+        pb.inc(1);
+    }
+    pb.finish();
+
+    for column in T::INDEXES {
+        tx.execute_batch(&format!(
+            "CREATE INDEX {} ON {} ({})",
+            quote(&format!(
+                "{}_{}",
+                T::TABLE,
+                column.to_lowercase().replace(' ', "_")
+            )),
+            quote(T::TABLE),
+            quote(column)
+        ))?;
+    }
+    Ok(())
+}
+
+// The columns of License from AM, the licensee (entity type L) from EN, and HD, as in the
+// MySQL view, which is also always an inner join.
+fn create_license_view(tx: &Transaction) -> rusqlite::Result<()> {
+    let columns: Vec<String> = crate::License::COLUMNS
+        .iter()
+        .map(|(_field, column)| {
+            format!("{}.{}", schema::license_column_alias(column), quote(column))
+        })
+        .collect();
+    tx.execute_batch(&format!(
+        "CREATE VIEW ham_license AS SELECT {} FROM {} am \
+         JOIN {} en ON en.\"Unique System Identifier\" = am.\"Unique System Identifier\" \
+         AND en.\"Entity Type\" = 'L' \
+         JOIN {} hd ON hd.\"Unique System Identifier\" = am.\"Unique System Identifier\"",
+        columns.join(", "),
+        quote(data::Amateur::TABLE),
+        quote(data::Entity::TABLE),
+        quote(data::ApplicationLicenseHeader::TABLE)
+    ))
+}
+
+pub fn write_database(
+    filename: &str,
+    amateur: &[data::Amateur],
+    entity: &[data::Entity],
+    application_license_header: &[data::ApplicationLicenseHeader],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_filename = format!("{}.tmp", filename);
+    if std::path::Path::new(&tmp_filename).exists() {
+        std::fs::remove_file(&tmp_filename)?;
+    }
+
+    let mut conn = Connection::open(&tmp_filename)?;
+    let tx = conn.transaction()?;
+    write_table(&tx, amateur)?;
+    write_table(&tx, entity)?;
+    write_table(&tx, application_license_header)?;
+    create_license_view(&tx)?;
+    tx.commit()?;
+    conn.close().map_err(|(_conn, why)| why)?;
+
+    std::fs::rename(&tmp_filename, filename)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn count(conn: &Connection, table: &str) -> i64 {
+        conn.query_row(
+            &format!("SELECT COUNT(*) FROM {}", quote(table)),
+            [],
+            |row| row.get(0),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn writes_tables_and_license_view() {
+        let directory = tempfile::tempdir().unwrap();
+        let filename = directory.path().join("fccdb.sqlite");
+        let filename = filename.to_str().unwrap();
+        let fcc_db = fixtures::fcc_db();
+        // A second write replaces the first.
+        for _ in 0..2 {
+            write_database(
+                filename,
+                &fcc_db.amateur,
+                &fcc_db.entity,
+                &fcc_db.application_license_header,
+            )
+            .unwrap();
+        }
+
+        let conn = Connection::open(filename).unwrap();
+        assert_eq!(count(&conn, "ham_AM"), 3);
+        assert_eq!(count(&conn, "ham_EN"), 4);
+        assert_eq!(count(&conn, "ham_HD"), 3);
+
+        // Only 1001 and 1002 have AM, a licensee and HD; 1002's contact is left out.
+        let mut statement = conn
+            .prepare(
+                "SELECT \"Unique System Identifier\", \"Call Sign\", \"First Name\", \
+                 \"License Status\", \"Region Code\" FROM ham_license \
+                 ORDER BY \"Unique System Identifier\"",
+            )
+            .unwrap();
+        let licenses: Vec<(i64, String, String, String, Option<i64>)> = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            licenses,
+            [
+                (
+                    1001,
+                    "K1TBC".to_string(),
+                    "First1".to_string(),
+                    "A".to_string(),
+                    Some(1)
+                ),
+                (
+                    1002,
+                    "K2TCD".to_string(),
+                    "First2".to_string(),
+                    "E".to_string(),
+                    Some(2)
+                ),
+            ]
+        );
+        assert!(!std::path::Path::new(&format!("{}.tmp", filename)).exists());
+
+        // The view has the License columns, so no Record Type.
+        let statement = conn.prepare("SELECT * FROM ham_license").unwrap();
+        let columns: Vec<&str> = crate::License::COLUMNS
+            .iter()
+            .map(|(_field, column)| *column)
+            .collect();
+        assert_eq!(statement.column_names(), columns);
+    }
+}
@@ -0,0 +1,194 @@
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::config::JoinMode;
+use crate::data;
+use crate::filter::Filter;
+use crate::{FccDB, License};
+
+/*
+ * License counts for `libfcc-rust stats [--json] [SNAPSHOT|DIRECTORY]`.
+ *
+ * Counts every license, joined as with join: left, by operator class, license status, the
+ * licensee's state, call area (the first digit of the call sign), and applicant type, plus
+ * grants per month from the HD grant date.  --filter narrows the licenses counted, e.g.
+ * `stats --filter 'license_status == Active'`.  Without a path it reads input_snapshot, or the
+ * .dat files extracted into data/ by the last run.
+ */
+
+const BLANK: &str = "(blank)";
+
+#[derive(Serialize, Default)]
+pub struct Stats {
+    pub licenses: usize,
+    pub operator_class: BTreeMap<String, usize>,
+    pub license_status: BTreeMap<String, usize>,
+    pub state: BTreeMap<String, usize>,
+    pub call_area: BTreeMap<String, usize>,
+    pub applicant_type: BTreeMap<String, usize>,
+    pub grants_per_month: BTreeMap<String, usize>,
+}
+
+fn count(counts: &mut BTreeMap<String, usize>, key: &str) {
+    let key = match key.trim() {
+        "" => BLANK,
+        key => key,
+    };
+    *counts.entry(key.to_string()).or_default() += 1;
+}
+
+impl Stats {
+    pub fn add(&mut self, license: &License) {
+        self.licenses += 1;
+        let operator_class = match &license.amateur {
+            Some(amateur) => data::variant(&amateur.operator_class),
+            None => String::new(),
+        };
+        count(
+            &mut self.operator_class,
+            data::operator_class_name(&operator_class),
+        );
+        let call_area: String = license
+            .callsign()
+            .chars()
+            .find(char::is_ascii_digit)
+            .map(String::from)
+            .unwrap_or_default();
+        count(&mut self.call_area, &call_area);
+
+        match &license.licensee {
+            Some(licensee) => {
+                count(&mut self.state, &licensee.state);
+                let applicant_type =
+                    data::ApplicantTypeCode::from(licensee.applicant_type_code.as_str());
+                count(&mut self.applicant_type, &data::variant(&applicant_type));
+            }
+            None => {
+                count(&mut self.state, "");
+                count(&mut self.applicant_type, "");
+            }
+        }
+
+        match &license.header {
+            Some(header) => {
+                count(
+                    &mut self.license_status,
+                    &data::variant(&header.license_status),
+                );
+                let month = NaiveDate::parse_from_str(header.grant_date.trim(), "%m/%d/%Y")
+                    .map(|date| date.format("%Y-%m").to_string())
+                    .unwrap_or_default();
+                count(&mut self.grants_per_month, &month);
+            }
+            None => {
+                count(&mut self.license_status, "");
+                count(&mut self.grants_per_month, "");
+            }
+        }
+    }
+}
+
+pub fn stats(licenses: impl Iterator<Item = License>) -> Stats {
+    let mut stats = Stats::default();
+    for license in licenses {
+        stats.add(&license);
+    }
+    stats
+}
+
+fn print_section(title: &str, counts: &BTreeMap<String, usize>, total: usize, by_count: bool) {
+    let mut rows: Vec<(&String, &usize)> = counts.iter().collect();
+    if by_count {
+        rows.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+    }
+    println!("\n{}", title);
+    for (key, count) in rows {
+        let percent = 100.0 * *count as f64 / total.max(1) as f64;
+        println!("  {:<28} {:>9} {:>6.1}%", key, count, percent);
+    }
+}
+
+fn print_table(stats: &Stats) {
+    println!("Licenses: {}", stats.licenses);
+    print_section(
+        "Operator class",
+        &stats.operator_class,
+        stats.licenses,
+        true,
+    );
+    print_section(
+        "License status",
+        &stats.license_status,
+        stats.licenses,
+        true,
+    );
+    print_section("State", &stats.state, stats.licenses, true);
+    print_section("Call area", &stats.call_area, stats.licenses, false);
+    print_section(
+        "Applicant type",
+        &stats.applicant_type,
+        stats.licenses,
+        true,
+    );
+    print_section(
+        "Grants per month",
+        &stats.grants_per_month,
+        stats.licenses,
+        false,
+    );
+}
+
+// stats [--json] [SNAPSHOT|DIRECTORY]
+pub fn run(
+    args: &[String],
+    default_path: &str,
+    filter: &Filter,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--json").collect();
+    let path = match paths[..] {
+        [] => default_path,
+        [path] => path.as_str(),
+        _ => return Err("usage: libfcc-rust stats [--json] [SNAPSHOT|DIRECTORY]".into()),
+    };
+
+    let fcc_db = FccDB::load(path)?;
+    let licenses =
+        crate::iter_licenses(&fcc_db, &JoinMode::Left).filter(|license| filter.matches(license));
+    let stats = stats(licenses);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        print_table(&stats);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn counts_every_license_once_per_section() {
+        let stats = stats(fixtures::licenses(&JoinMode::Left).into_iter());
+        assert_eq!(
+            serde_json::to_value(&stats).unwrap(),
+            serde_json::json!({
+                "licenses": 4,
+                "operator_class": {
+                    "(blank)": 1,
+                    "Amateur Extra": 1,
+                    "General": 1,
+                    "Technician": 1,
+                },
+                "license_status": {"(blank)": 1, "Active": 1, "Cancelled": 1, "Expired": 1},
+                "state": {"(blank)": 1, "IA": 1, "MN": 1, "WI": 1},
+                "call_area": {"1": 1, "2": 1, "3": 1, "4": 1},
+                "applicant_type": {"(blank)": 1, "AmateurClub": 1, "Individual": 2},
+                "grants_per_month": {"(blank)": 1, "2012-03": 1, "2014-05": 1, "2021-02": 1},
+            })
+        );
+    }
+}
@@ -4,7 +4,7 @@ use std::io;
 pub fn unzip_uls() {
     let src_path = "data/l_amat.zip";
     let fname = std::path::Path::new(&src_path);
-    let file = fs::File::open(&fname).unwrap();
+    let file = fs::File::open(fname).unwrap();
 
     let mut archive = zip::ZipArchive::new(file).unwrap();
 
@@ -31,7 +31,7 @@ pub fn unzip_uls() {
 
         if (*file.name()).ends_with('/') {
             println!("File {} extracted to \"{}\"", i, real_outpath);
-            fs::create_dir_all(&real_outpath_obj).unwrap();
+            fs::create_dir_all(real_outpath_obj).unwrap();
         } else {
             println!(
                 "File {} extracted to \"{}\" ({} bytes)",
@@ -41,10 +41,10 @@ pub fn unzip_uls() {
             );
             if let Some(p) = real_outpath_obj.parent() {
                 if !p.exists() {
-                    fs::create_dir_all(&p).unwrap();
+                    fs::create_dir_all(p).unwrap();
                 }
             }
-            let mut outfile = fs::File::create(&real_outpath_obj).unwrap();
+            let mut outfile = fs::File::create(real_outpath_obj).unwrap();
             io::copy(&mut file, &mut outfile).unwrap();
         }
 
@@ -54,8 +54,25 @@ pub fn unzip_uls() {
             use std::os::unix::fs::PermissionsExt;
 
             if let Some(mode) = file.unix_mode() {
-                fs::set_permissions(&real_outpath_obj, fs::Permissions::from_mode(mode)).unwrap();
+                fs::set_permissions(real_outpath_obj, fs::Permissions::from_mode(mode)).unwrap();
             }
         }
     }
 }
+
+// When the FCC built the extract, taken from the AM.dat entry in the zip.  The FCC stamps its
+// files in US Eastern time, so the result carries no offset.
+pub fn source_timestamp() -> Option<String> {
+    let file = fs::File::open("data/l_amat.zip").ok()?;
+    let mut archive = zip::ZipArchive::new(file).ok()?;
+    let modified = archive.by_name("AM.dat").ok()?.last_modified()?;
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        modified.year(),
+        modified.month(),
+        modified.day(),
+        modified.hour(),
+        modified.minute(),
+        modified.second()
+    ))
+}
@@ -0,0 +1,346 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::config::{NotifyTarget, WatchlistConfig};
+use crate::diff::{self, Change, LicenseSummary};
+use crate::License;
+
+/*
+ * Watchlist notifications.
+ *
+ * Licenses whose call sign, FRN or ZIP code (matched on its first five digits, or however
+ * many are listed) is on the watchlist are summarized into a small JSON state file after each
+ * run.  The next run compares against it and sends what changed, as diff reports it, to
+ * stdout, a JSON file and/or a webhook.  The first run only records the state.  A license that
+ * starts matching later, by a watchlist edit or a move into a watched ZIP code, is recorded
+ * silently too, unless it was granted after the extract the saved state came from, in which
+ * case it is reported as new.  The state is saved after the notifications go out, so a failed
+ * webhook is retried on the next run.
+ */
+
+#[derive(Serialize, Deserialize, Default)]
+struct WatchState {
+    source_timestamp: Option<String>,
+    licenses: BTreeMap<u32, LicenseSummary>,
+}
+
+#[derive(Serialize)]
+struct Notification<'a> {
+    source_timestamp: Option<&'a str>,
+    changes: &'a [Change],
+}
+
+fn is_watched(watchlist: &WatchlistConfig, summary: &LicenseSummary) -> bool {
+    watchlist
+        .callsigns
+        .iter()
+        .any(|callsign| callsign.eq_ignore_ascii_case(&summary.callsign))
+        || watchlist.frns.contains(&summary.frn)
+        || watchlist
+            .zip_codes
+            .iter()
+            .any(|zip_code| !zip_code.is_empty() && summary.zip_code.starts_with(zip_code.as_str()))
+}
+
+// Whether the license was granted after the day of the extract the state was saved from.
+fn granted_since(summary: &LicenseSummary, source_timestamp: Option<&str>) -> bool {
+    let since = source_timestamp
+        .and_then(|timestamp| timestamp.get(..10))
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+    let granted = NaiveDate::parse_from_str(&summary.grant_date, "%m/%d/%Y").ok();
+    match (granted, since) {
+        (Some(granted), Some(since)) => granted > since,
+        _ => false,
+    }
+}
+
+fn read_state(filename: &str) -> Result<Option<WatchState>, Box<dyn std::error::Error>> {
+    if !Path::new(filename).exists() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_reader(File::open(filename)?)?))
+}
+
+fn write_json<T: Serialize>(filename: &str, value: &T) -> Result<(), Box<dyn std::error::Error>> {
+    let tmp_filename = format!("{}.tmp", filename);
+    let mut out = BufWriter::new(File::create(&tmp_filename)?);
+    serde_json::to_writer_pretty(&mut out, value)?;
+    out.write_all(b"\n")?;
+    out.into_inner()?.sync_all()?;
+    std::fs::rename(&tmp_filename, filename)?;
+    Ok(())
+}
+
+async fn post(
+    url: &str,
+    notification: &Notification<'_>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let res = reqwest::Client::new()
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(serde_json::to_vec(notification)?)
+        .send()
+        .await
+        .or(Err(format!("Failed to POST to '{}'", url)))?;
+    if !res.status().is_success() {
+        return Err(format!("POST to '{}' returned {}", url, res.status()).into());
+    }
+    Ok(())
+}
+
+// Compares the watched licenses with the saved state, notifies, and saves the new state.
+pub async fn check(
+    watchlist: &WatchlistConfig,
+    licenses: impl Iterator<Item = License>,
+    source_timestamp: Option<&str>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let old = read_state(&watchlist.state_file)?;
+    let old_licenses = old.as_ref().map(|state| &state.licenses);
+    let since = old
+        .as_ref()
+        .and_then(|state| state.source_timestamp.as_deref());
+
+    let mut state = WatchState {
+        source_timestamp: source_timestamp.map(str::to_string),
+        licenses: BTreeMap::new(),
+    };
+    let mut changes: Vec<Change> = Vec::new();
+    let mut seen: HashSet<u32> = HashSet::new();
+    for license in licenses {
+        let summary = LicenseSummary::new(&license);
+        let before = old_licenses.and_then(|old| old.get(&summary.unique_system_identifier));
+        let watched = is_watched(watchlist, &summary);
+        if before.is_some() {
+            seen.insert(summary.unique_system_identifier);
+        }
+        // A license that moves out of a watched ZIP code is reported once, then dropped.
+        let new = watched && before.is_none() && granted_since(&summary, since);
+        if old_licenses.is_some() && (before.is_some() || new) {
+            changes.extend(diff::compare(before, Some(&summary)));
+        }
+        if watched {
+            state
+                .licenses
+                .insert(summary.unique_system_identifier, summary);
+        }
+    }
+    for (unique_system_identifier, summary) in old_licenses.into_iter().flatten() {
+        if !seen.contains(unique_system_identifier) {
+            changes.extend(diff::compare(Some(summary), None));
+        }
+    }
+    changes.sort_by(|a, b| (a.kind, &a.callsign).cmp(&(b.kind, &b.callsign)));
+
+    if old.is_none() {
+        println!(
+            "Watchlist: recorded {} licenses in {}",
+            state.licenses.len(),
+            watchlist.state_file
+        );
+    }
+    let notification = Notification {
+        source_timestamp,
+        changes: &changes,
+    };
+    for target in &watchlist.notify {
+        match target {
+            NotifyTarget::Stdout => {
+                for change in &changes {
+                    println!(
+                        "Watchlist: {}: {}",
+                        change.kind.title(),
+                        diff::describe(change)
+                    );
+                }
+            }
+            NotifyTarget::File => write_json(&watchlist.notifications_file, &notification)?,
+            NotifyTarget::Webhook if changes.is_empty() => (),
+            NotifyTarget::Webhook => {
+                let url = watchlist
+                    .webhook_url
+                    .as_ref()
+                    .ok_or("watchlist notifies a webhook but webhook_url is missing")?;
+                post(url, &notification).await?;
+            }
+        }
+    }
+
+    write_json(&watchlist.state_file, &state)?;
+    Ok(changes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::JoinMode;
+    use crate::{data, fixtures};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    // Answers one request with the status and returns the request body.
+    async fn webhook(status: u16) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _address) = listener.accept().await.unwrap();
+            let mut request: Vec<u8> = Vec::new();
+            let mut buffer = [0u8; 4096];
+            let body = loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).into_owned();
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length: usize = head
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= length || read == 0 {
+                        break body.to_string();
+                    }
+                }
+            };
+            let response = format!(
+                "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            body
+        });
+        (url, handle)
+    }
+
+    fn watchlist(directory: &Path, callsigns: &[&str], url: Option<String>) -> WatchlistConfig {
+        let path = |name: &str| directory.join(name).to_string_lossy().into_owned();
+        WatchlistConfig {
+            callsigns: callsigns
+                .iter()
+                .map(|callsign| callsign.to_string())
+                .collect(),
+            frns: Vec::new(),
+            zip_codes: Vec::new(),
+            state_file: path("state.json"),
+            notify: match url {
+                Some(_) => vec![NotifyTarget::File, NotifyTarget::Webhook],
+                None => vec![NotifyTarget::File],
+            },
+            notifications_file: path("notifications.json"),
+            webhook_url: url,
+        }
+    }
+
+    fn licenses_except(unique_system_identifier: u32) -> impl Iterator<Item = License> {
+        fixtures::licenses(&JoinMode::Left)
+            .into_iter()
+            .filter(move |license| license.unique_system_identifier() != unique_system_identifier)
+    }
+
+    fn saved_callsigns(watchlist: &WatchlistConfig) -> Vec<String> {
+        let state = read_state(&watchlist.state_file).unwrap().unwrap();
+        state
+            .licenses
+            .values()
+            .map(|summary| summary.callsign.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn first_run_only_records() {
+        let directory = tempfile::tempdir().unwrap();
+        let watchlist = watchlist(directory.path(), &["K1TBC", "K2TCD"], None);
+        let licenses = fixtures::licenses(&JoinMode::Left);
+        let changes = check(
+            &watchlist,
+            licenses.into_iter(),
+            Some("2026-10-01T00:00:00"),
+        );
+        assert_eq!(changes.await.unwrap(), 0);
+        assert_eq!(saved_callsigns(&watchlist), ["K1TBC", "K2TCD"]);
+    }
+
+    #[tokio::test]
+    async fn newly_watched_license_is_recorded_silently() {
+        let directory = tempfile::tempdir().unwrap();
+        let licenses = || fixtures::licenses(&JoinMode::Left).into_iter();
+        let before = watchlist(directory.path(), &["K1TBC"], None);
+        check(&before, licenses(), Some("2026-10-01T00:00:00"))
+            .await
+            .unwrap();
+
+        // K2TCD was granted in 2012, long before the saved state.
+        let after = watchlist(directory.path(), &["K1TBC", "K2TCD"], None);
+        let changes = check(&after, licenses(), Some("2026-10-08T00:00:00"));
+        assert_eq!(changes.await.unwrap(), 0);
+        assert_eq!(saved_callsigns(&after), ["K1TBC", "K2TCD"]);
+    }
+
+    #[tokio::test]
+    async fn webhook_receives_new_licenses_and_upgrades() {
+        let directory = tempfile::tempdir().unwrap();
+        let local = watchlist(directory.path(), &["K1TBC", "K2TCD"], None);
+        check(&local, licenses_except(1002), Some("2026-10-01T00:00:00"))
+            .await
+            .unwrap();
+
+        let mut licenses = fixtures::licenses(&JoinMode::Left);
+        for license in &mut licenses {
+            match license.unique_system_identifier() {
+                1001 => {
+                    license.amateur.as_mut().unwrap().operator_class = data::OperatorClass::General
+                }
+                1002 => license.header.as_mut().unwrap().grant_date = "10/05/2026".to_string(),
+                _ => (),
+            }
+        }
+        let (url, request) = webhook(200).await;
+        let remote = watchlist(directory.path(), &["K1TBC", "K2TCD"], Some(url));
+        let changes = check(&remote, licenses.into_iter(), Some("2026-10-08T00:00:00"));
+        assert_eq!(changes.await.unwrap(), 2);
+
+        let body: serde_json::Value = serde_json::from_str(&request.await.unwrap()).unwrap();
+        assert_eq!(body["source_timestamp"], "2026-10-08T00:00:00");
+        let kinds: Vec<(&str, &str)> = body["changes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|change| {
+                (
+                    change["kind"].as_str().unwrap(),
+                    change["callsign"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(kinds, [("new", "K2TCD"), ("upgrade", "K1TBC")]);
+        let file: serde_json::Value =
+            serde_json::from_reader(File::open(&remote.notifications_file).unwrap()).unwrap();
+        assert_eq!(file, body);
+    }
+
+    #[tokio::test]
+    async fn failed_webhook_keeps_the_old_state() {
+        let directory = tempfile::tempdir().unwrap();
+        let local = watchlist(directory.path(), &["K1TBC"], None);
+        check(
+            &local,
+            fixtures::licenses(&JoinMode::Left).into_iter(),
+            Some("2026-10-01T00:00:00"),
+        )
+        .await
+        .unwrap();
+
+        let (url, request) = webhook(500).await;
+        let remote = watchlist(directory.path(), &["K1TBC"], Some(url));
+        let changes = check(&remote, licenses_except(1001), Some("2026-10-08T00:00:00"));
+        assert!(changes.await.is_err());
+        assert!(request.await.unwrap().contains("\"removed\""));
+        assert_eq!(saved_callsigns(&remote), ["K1TBC"]);
+    }
+}
@@ -0,0 +1,50 @@
+use libfcc_rust::config::JoinMode;
+use libfcc_rust::sql::SqlRow;
+use libfcc_rust::{columnar, iter_licenses, parse, snapshot, FccDB, License};
+
+fn fcc_db() -> FccDB {
+    FccDB {
+        amateur: vec![parse::parse_am_line(
+            "AM|1001|||K1TBC|T|D|1||||||||||".to_string(),
+        )],
+        entity: vec![parse::parse_en_line(
+            "EN|1001|||K1TBC|L|||First1|Q|Last1||||u1@example.com|1 Main St|City1|WI|55401||||0000000001|I|||A|01/02/2020|".to_string(),
+        )],
+        application_license_header: vec![parse::parse_hd_line(
+            "HD|1001|||K1TBC|A|HA|02/15/2021|02/15/2031||||||||||||||||||||N||||||||||||||||1||||||||||||||".to_string(),
+        )],
+    }
+}
+
+#[test]
+fn snapshot_loads_into_record_batches() {
+    let directory = tempfile::tempdir().unwrap();
+    let filename = directory.path().join("fccdb.dat");
+    let filename = filename.to_str().unwrap();
+    snapshot::write_snapshot(filename, &fcc_db(), Some("2026-10-18".to_string())).unwrap();
+
+    let fcc_db = FccDB::load_snapshot(filename).unwrap();
+    let licenses: Vec<License> = iter_licenses(&fcc_db, &JoinMode::Inner).collect();
+    assert_eq!(licenses.len(), 1);
+    assert_eq!(licenses[0].callsign(), "K1TBC");
+
+    let batch = columnar::record_batch(&licenses).unwrap();
+    assert_eq!(batch.num_rows(), 1);
+    assert_eq!(batch.schema(), columnar::schema::<License>());
+    assert_eq!(batch.num_columns(), License::COLUMNS.len());
+
+    let batch = columnar::record_batch(&fcc_db.entity).unwrap();
+    assert_eq!(
+        batch.schema(),
+        columnar::schema::<libfcc_rust::data::Entity>()
+    );
+    let batches: Vec<_> = columnar::record_batches(iter_licenses(&fcc_db, &JoinMode::Inner))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].num_rows(), 1);
+
+    let header = snapshot::read_header(filename).unwrap();
+    assert_eq!(header.source_timestamp.as_deref(), Some("2026-10-18"));
+    assert_eq!(header.amateur_count, 1);
+}